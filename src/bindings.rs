@@ -1,12 +1,13 @@
 use std::{
 	collections::{hash_map, HashMap},
-	fmt::Debug,
+	fmt::{Debug, Display},
 };
 
 use crossterm::event::{KeyCode, KeyModifiers};
 use serde::{Deserialize, Serialize};
 
 use crate::{
+	format::TableFormat,
 	input::{Input, InputBuffer},
 	program::{Action, Direction},
 };
@@ -26,17 +27,48 @@ impl<A> BindNode<A> {
 			_ => None,
 		}
 	}
+}
 
-	pub fn bindings(&self) -> Option<&Bindings<A>> {
-		match self {
-			BindNode::Chord { bindings, .. } => Some(bindings),
-			_ => None,
+#[derive(Debug)]
+pub struct Bindings<A>(BindMap<A>);
+
+/// A binding that replaced others
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict<A> {
+	/// Keys of the new binding
+	pub keys: InputBuffer,
+	/// Action of the new binding, or none if it starts a chord
+	pub action: Option<A>,
+	/// Bindings of the same keys, of a prefix of them that became a chord, or of a chord they
+	/// started that was replaced
+	pub replaced: Vec<(InputBuffer, A)>,
+}
+
+impl<A> Conflict<A> {
+	fn new(keys: &[&Input], action: Option<A>, replaced: Vec<(InputBuffer, A)>) -> Self {
+		Self {
+			keys: keys.iter().copied().copied().collect(),
+			action,
+			replaced,
 		}
 	}
 }
 
-#[derive(Debug)]
-pub struct Bindings<A>(BindMap<A>);
+impl<A: Debug> Display for Conflict<A> {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match &self.action {
+			Some(action) => write!(f, "{} ({action:?}) replaces ", self.keys)?,
+			None => write!(f, "Chord {} replaces ", self.keys)?,
+		}
+		for (i, (keys, action)) in self.replaced.iter().enumerate() {
+			if i != 0 {
+				write!(f, ", ")?;
+			}
+			write!(f, "{keys} ({action:?})")?;
+		}
+		Ok(())
+	}
+}
 
 impl<A> Serialize for Bindings<A>
 where
@@ -61,13 +93,19 @@ where
 	{
 		let list = Vec::<(Input, BindNode<A>)>::deserialize(deserializer)?;
 		let mut value = HashMap::new();
-		value.extend(list.into_iter());
+		value.extend(list);
 		Ok(Bindings(value))
 	}
 }
 
 impl Default for Bindings<Action> {
 	fn default() -> Self {
+		Self::defaults().expect("Default bindings conflict")
+	}
+}
+
+impl Bindings<Action> {
+	fn defaults() -> Result<Self, Conflict<Action>> {
 		use Action as A;
 		use Direction as D;
 		use KeyCode::*;
@@ -76,50 +114,130 @@ impl Default for Bindings<Action> {
 		let mut s = Self::empty();
 
 		// movement
-		s.insert(Input(Up, none), A::Move(D::Up));
-		s.insert(Input(Down, none), A::Move(D::Down));
-		s.insert(Input(Left, none), A::Move(D::Left));
-		s.insert(Input(Right, none), A::Move(D::Right));
-		s.insert(Input(Tab, none), A::Move(D::Right));
-		s.insert(Input(Char('k'), none), A::Move(D::Up));
-		s.insert(Input(Char('j'), none), A::Move(D::Down));
-		s.insert(Input(Char('h'), none), A::Move(D::Left));
-		s.insert(Input(Char('l'), none), A::Move(D::Right));
-		s.insert(Input(Home, KeyModifiers::CONTROL), A::Home);
-		s.insert(Input(End, KeyModifiers::CONTROL), A::End);
-		s.insert(Input(Home, none), A::HomeRow);
-		s.insert(Input(End, none), A::EndRow);
-		s.insert(Input(PageUp, none), A::Jump(D::Up));
-		s.insert(Input(PageDown, none), A::Jump(D::Down));
-		s.insert(Input(PageUp, KeyModifiers::ALT), A::Jump(D::Left));
-		s.insert(Input(PageDown, KeyModifiers::ALT), A::Jump(D::Right));
-		s.insert(Input(Char('g'), KeyModifiers::CONTROL), A::GoTo);
-
-		s.insert(Input(Char('c'), KeyModifiers::CONTROL), A::Quit);
-		s.insert(Input(Char('s'), KeyModifiers::CONTROL), A::Write);
-		s.insert(Input(Char('r'), KeyModifiers::CONTROL), A::Read);
-		s.insert(Input(Char('z'), KeyModifiers::CONTROL), A::Undo);
-		s.insert(Input(Char('y'), KeyModifiers::CONTROL), A::Redo);
-		s.insert(Input(Backspace, none), A::Clear);
-		s.insert(Input(Delete, none), A::Clear);
-		s.insert(Input(F(2), none), A::Edit);
-		s.insert(Input(Enter, none), A::Replace);
-		s.insert(Input(F(12), none), A::ToggleDebug);
-		s.insert(Input(F(1), none), A::TogglePalette);
-
-		let delete = s.create_chord("Delete", &[Input(Char('-'), KeyModifiers::ALT)]);
-		delete.insert(Input(Char('c'), none), A::DeleteCol);
-		delete.insert(Input(Char('r'), none), A::DeleteRow);
-
-		let insert = s.create_chord("Insert", &[Input(Char('+'), KeyModifiers::ALT)]);
-		insert.insert(Input(Char('c'), none), A::InsertCol);
-		insert.insert(Input(Char('r'), none), A::InsertRow);
+		s.insert(Input(Up, none), A::Move(D::Up))?;
+		s.insert(Input(Down, none), A::Move(D::Down))?;
+		s.insert(Input(Left, none), A::Move(D::Left))?;
+		s.insert(Input(Right, none), A::Move(D::Right))?;
+		s.insert(Input(Tab, none), A::Move(D::Right))?;
+		s.insert(Input(Char('k'), none), A::Move(D::Up))?;
+		s.insert(Input(Char('j'), none), A::Move(D::Down))?;
+		s.insert(Input(Char('h'), none), A::Move(D::Left))?;
+		s.insert(Input(Char('l'), none), A::Move(D::Right))?;
+		s.insert(Input(Home, KeyModifiers::CONTROL), A::Home)?;
+		s.insert(Input(End, KeyModifiers::CONTROL), A::End)?;
+		s.insert(Input(Home, none), A::HomeRow)?;
+		s.insert(Input(End, none), A::EndRow)?;
+		s.insert(Input(PageUp, none), A::Jump(D::Up))?;
+		s.insert(Input(PageDown, none), A::Jump(D::Down))?;
+		s.insert(Input(PageUp, KeyModifiers::ALT), A::Jump(D::Left))?;
+		s.insert(Input(PageDown, KeyModifiers::ALT), A::Jump(D::Right))?;
+		s.insert(Input(Char('g'), KeyModifiers::CONTROL), A::GoTo)?;
+
+		s.insert(Input(Char('c'), KeyModifiers::CONTROL), A::Quit)?;
+		s.insert(Input(Esc, none), A::Cancel)?;
+		s.insert(Input(Char('t'), KeyModifiers::CONTROL), A::ToggleFollow)?;
+		s.insert(Input(PageDown, KeyModifiers::CONTROL), A::NextBuffer)?;
+		s.insert(Input(PageUp, KeyModifiers::CONTROL), A::PrevBuffer)?;
+		s.insert(Input(Char('b'), KeyModifiers::ALT), A::ListBuffers)?;
+		s.insert(Input(Char('s'), KeyModifiers::CONTROL), A::Write)?;
+		s.insert(Input(Char('r'), KeyModifiers::CONTROL), A::Read)?;
+		s.insert(Input(Char('z'), KeyModifiers::CONTROL), A::Undo)?;
+		s.insert(Input(Char('y'), KeyModifiers::CONTROL), A::Redo)?;
+		s.insert(Input(F(4), none), A::Repeat)?;
+		s.insert(Input(Char('q'), KeyModifiers::ALT), A::RecordMacro)?;
+		s.insert(Input(Char('@'), KeyModifiers::ALT), A::PlayMacro)?;
+		s.insert(Input(Backspace, none), A::Clear)?;
+		s.insert(Input(Delete, none), A::Clear)?;
+		s.insert(Input(F(2), none), A::Edit)?;
+		s.insert(Input(Enter, none), A::Replace)?;
+		s.insert(Input(F(12), none), A::ToggleDebug)?;
+		s.insert(Input(F(1), none), A::TogglePalette)?;
+		s.insert(Input(Char('v'), none), A::Select)?;
+
+		let delete = s.create_chord("Delete", &[Input(Char('-'), KeyModifiers::ALT)])?;
+		delete.insert(Input(Char('c'), none), A::DeleteCol)?;
+		delete.insert(Input(Char('r'), none), A::DeleteRow)?;
+
+		let insert = s.create_chord("Insert", &[Input(Char('+'), KeyModifiers::ALT)])?;
+		insert.insert(Input(Char('c'), none), A::InsertCol)?;
+		insert.insert(Input(Char('r'), none), A::InsertRow)?;
+
+		let export = s.create_chord("Export", &[Input(Char('e'), KeyModifiers::ALT)])?;
+		export.insert(Input(Char('m'), none), A::Export(TableFormat::Markdown))?;
+		export.insert(Input(Char('h'), none), A::Export(TableFormat::Html))?;
+		export.insert(Input(Char('l'), none), A::Export(TableFormat::Latex))?;
+		export.insert(Input(Char('s'), none), A::Export(TableFormat::Sql))?;
+
+		let copy = s.create_chord("Copy as", &[Input(Char('y'), KeyModifiers::ALT)])?;
+		copy.insert(Input(Char('m'), none), A::CopyAs(TableFormat::Markdown))?;
+		copy.insert(Input(Char('h'), none), A::CopyAs(TableFormat::Html))?;
+		copy.insert(Input(Char('l'), none), A::CopyAs(TableFormat::Latex))?;
+		copy.insert(Input(Char('s'), none), A::CopyAs(TableFormat::Sql))?;
+
+		let window = s.create_chord("Window", &[Input(Char('w'), KeyModifiers::CONTROL)])?;
+		window.insert(Input(Char('s'), none), A::SplitHorizontal)?;
+		window.insert(Input(Char('v'), none), A::SplitVertical)?;
+		window.insert(Input(Char('q'), none), A::ClosePane)?;
+		window.insert(Input(Char('w'), none), A::NextPane)?;
+		window.insert(Input(Char('W'), none), A::PrevPane)?;
+		window.insert(Input(Char('b'), none), A::ToggleSyncScroll)?;
+
+		Ok(s)
+	}
+
+	/// Vim-style bindings for normal mode, replacing some of the defaults
+	pub fn vim() -> Self {
+		use Action as A;
+		let mut s = Self::default();
+		for (keys, action) in [
+			("i", A::EditFromStart),
+			("a", A::Edit),
+			("c", A::Replace),
+			("x", A::Clear),
+			("d d", A::DeleteRow),
+			("y y", A::Yank),
+			("p", A::PasteBelow),
+			("P", A::PasteAbove),
+			("g g", A::HomeCol),
+			("G", A::EndCol),
+			("0", A::HomeRow),
+			("$", A::EndRow),
+			("u", A::Undo),
+			("<C-r>", A::Redo),
+			(".", A::Repeat),
+			("q", A::RecordMacro),
+			("@", A::PlayMacro),
+			(":", A::TogglePalette),
+		] {
+			s.bind(&parse_keys(keys), action);
+		}
+		s.name_chord(&parse_keys("d"), "Delete");
+		s.name_chord(&parse_keys("y"), "Yank");
+		s.name_chord(&parse_keys("g"), "Go to");
+		s
+	}
 
+	/// Vim-style bindings for visual mode, acting on the selected range
+	pub fn vim_visual() -> Self {
+		use Action as A;
+		let mut s = Self::vim();
+		for (keys, action) in [
+			("d", A::Clear),
+			("x", A::Clear),
+			("y", A::Yank),
+			("<Esc>", A::Select),
+		] {
+			s.bind(&parse_keys(keys), action);
+		}
 		s
 	}
 }
 
-impl<A: Debug> Bindings<A> {
+fn parse_keys(keys: &str) -> InputBuffer {
+	keys.parse().expect("Valid keys")
+}
+
+impl<A: Debug + Clone> Bindings<A> {
 	pub fn empty() -> Self {
 		Self(Default::default())
 	}
@@ -138,11 +256,10 @@ impl<A: Debug> Bindings<A> {
 		&'a self,
 		inputs: impl IntoIterator<Item = &'b Input>,
 	) -> Option<&'a BindNode<A>> {
-		let mut inputs = inputs.into_iter().peekable();
+		let mut inputs = inputs.into_iter();
 
 		let mut node = self.0.get(inputs.next()?)?;
-		loop {
-			let Some(input) = inputs.next() else { break };
+		for input in inputs {
 			match node {
 				BindNode::Action(_) => return Some(node), // TODO: decide if exiting earlier here is correct
 				BindNode::Chord { bindings, .. } => node = bindings.0.get(input)?,
@@ -151,60 +268,155 @@ impl<A: Debug> Bindings<A> {
 		Some(node)
 	}
 
-	pub fn insert(&mut self, k: Input, v: A) {
-		if let Some(n) = self.0.get(&k) {
-			panic!("Input already bound: {k:?} => {n:?}");
-		}
-		self.0.insert(k, BindNode::Action(v));
+	/// Bind `k` to `v`, unless it's already bound.
+	pub fn insert(&mut self, k: Input, v: A) -> Result<(), Conflict<A>> {
+		self.insert_chorded(&[k], v)
 	}
 
+	/// Create a chord named `name` started by `ks`, unless a prefix of it is bound to an action,
+	/// returning its bindings.
 	pub fn create_chord<'a>(
 		&mut self,
 		name: &str,
 		ks: impl IntoIterator<Item = &'a Input>,
-	) -> &mut Bindings<A> {
-		let mut map = self;
-		let mut ks = ks.into_iter().peekable();
-		assert!(ks.peek().is_some());
-		loop {
-			let Some(k) = ks.next() else { break };
-			match map.0.entry(*k).or_insert(BindNode::Chord {
-				name: if ks.peek().is_none() {
-					name.to_string()
-				} else {
-					Default::default()
-				},
-				bindings: Self::empty(),
-			}) {
-				BindNode::Action(a) => panic!("Chord conflicts with existing: {a:?}"),
-				BindNode::Chord { bindings, .. } => map = bindings,
+	) -> Result<&mut Bindings<A>, Conflict<A>> {
+		let ks: Vec<_> = ks.into_iter().collect();
+		assert!(!ks.is_empty());
+		let replaced = self.replaced_by(&ks, true);
+		if !replaced.is_empty() {
+			return Err(Conflict::new(&ks, None, replaced));
+		}
+		self.name_chord(ks.iter().copied(), name);
+		Ok(self.chord_mut(ks))
+	}
+
+	/// Bind `ks` to `v`, unless it or a prefix of it is already bound.
+	fn insert_chorded<'a>(
+		&mut self,
+		ks: impl IntoIterator<Item = &'a Input>,
+		v: A,
+	) -> Result<(), Conflict<A>> {
+		let ks: Vec<_> = ks.into_iter().collect();
+		let replaced = self.replaced_by(&ks, false);
+		if !replaced.is_empty() {
+			return Err(Conflict::new(&ks, Some(v), replaced));
+		}
+		self.bind(ks, v);
+		Ok(())
+	}
+
+	/// Bind `ks` to `v`, replacing any binding of it or of a prefix of it, and returning what was
+	/// replaced.
+	pub fn bind<'a>(
+		&mut self,
+		ks: impl IntoIterator<Item = &'a Input>,
+		v: A,
+	) -> Option<Conflict<A>> {
+		let mut ks: Vec<_> = ks.into_iter().collect();
+		let replaced = self.replaced_by(&ks, false);
+		let conflict =
+			(!replaced.is_empty()).then(|| Conflict::new(&ks, Some(v.clone()), replaced));
+		let last = *ks.pop().expect("Keys to bind");
+		self.chord_mut(ks).0.insert(last, BindNode::Action(v));
+		conflict
+	}
+
+	/// Name the chord started by `ks`, replacing any binding of it or of a prefix of it, and
+	/// returning what was replaced.
+	pub fn name_chord<'a>(
+		&mut self,
+		ks: impl IntoIterator<Item = &'a Input>,
+		name: &str,
+	) -> Option<Conflict<A>> {
+		let mut ks: Vec<_> = ks.into_iter().collect();
+		let replaced = self.replaced_by(&ks, true);
+		let conflict = (!replaced.is_empty()).then(|| Conflict::new(&ks, None, replaced));
+		let last = *ks.pop().expect("Keys of chord");
+		let map = &mut self.chord_mut(ks).0;
+		match map.get_mut(&last) {
+			Some(BindNode::Chord { name: n, .. }) => *n = name.to_string(),
+			_ => {
+				map.insert(
+					last,
+					BindNode::Chord {
+						name: name.to_string(),
+						bindings: Self::empty(),
+					},
+				);
 			}
 		}
-		map
+		conflict
 	}
 
-	fn insert_chorded<'a>(&mut self, ks: impl IntoIterator<Item = &'a Input>, v: A) {
+	/// Bindings that binding `ks` to an action, or to a chord if `chord`, would replace
+	fn replaced_by(&self, ks: &[&Input], chord: bool) -> Vec<(InputBuffer, A)> {
+		let mut replaced = Vec::new();
+		let mut prefix = InputBuffer::default();
 		let mut map = self;
-		let mut ks = ks.into_iter().peekable();
-		assert!(ks.peek().is_some());
-		loop {
-			let Some(k) = ks.next() else { break };
-			if ks.peek().is_none() {
-				if let Some(a) = map.0.get(k) {
-					panic!("Chord already bound: {a:?}");
+		for (i, k) in ks.iter().enumerate() {
+			prefix.push(**k);
+			let last = i == ks.len() - 1;
+			match map.0.get(k) {
+				None => break,
+				Some(BindNode::Action(a)) => {
+					replaced.push((prefix, a.clone()));
+					break;
 				}
-				map.0.insert(*k, BindNode::Action(v));
-				break;
-			} else {
-				match map.0.entry(*k).or_insert(BindNode::Chord {
+				Some(BindNode::Chord { bindings, .. }) if last && !chord => {
+					replaced.extend(bindings.iter().map(|(keys, a)| {
+						let mut full = prefix.clone();
+						full.extend(keys);
+						(full, a.clone())
+					}));
+					break;
+				}
+				Some(BindNode::Chord { bindings, .. }) => map = bindings,
+			}
+		}
+		replaced
+	}
+
+	/// Remove the binding of `ks`, along with any chords left empty, returning it if it existed.
+	pub fn unbind<'a>(&mut self, ks: impl IntoIterator<Item = &'a Input>) -> Option<BindNode<A>> {
+		let ks: Vec<_> = ks.into_iter().collect();
+		self.unbind_from(&ks)
+	}
+
+	fn unbind_from(&mut self, ks: &[&Input]) -> Option<BindNode<A>> {
+		let (first, rest) = ks.split_first()?;
+		if rest.is_empty() {
+			return self.0.remove(first);
+		}
+		let BindNode::Chord { bindings, .. } = self.0.get_mut(first)? else {
+			return None;
+		};
+		let removed = bindings.unbind_from(rest);
+		if bindings.0.is_empty() {
+			self.0.remove(first);
+		}
+		removed
+	}
+
+	/// Bindings of the chord started by `ks`, replacing actions bound to any prefix of it.
+	fn chord_mut(&mut self, ks: Vec<&Input>) -> &mut Bindings<A> {
+		let mut map = self;
+		for k in ks {
+			let node = map.0.entry(*k).or_insert(BindNode::Chord {
+				name: Default::default(),
+				bindings: Self::empty(),
+			});
+			if let BindNode::Action(_) = node {
+				*node = BindNode::Chord {
 					name: Default::default(),
 					bindings: Self::empty(),
-				}) {
-					BindNode::Action(a) => panic!("Chord conflicts with existing: {a:?}"),
-					BindNode::Chord { bindings, .. } => map = bindings,
-				}
+				};
 			}
+			let BindNode::Chord { bindings, .. } = node else {
+				unreachable!()
+			};
+			map = bindings;
 		}
+		map
 	}
 
 	pub fn singles(&self) -> impl Iterator<Item = (&Input, &A)> {
@@ -216,11 +428,6 @@ impl<A: Debug> Bindings<A> {
 	pub fn iter(&self) -> impl Iterator<Item = (InputBuffer, &A)> {
 		Iter::new(self)
 	}
-
-	pub fn actions(&self) -> impl Iterator<Item = &A> {
-		// TODO: standalone with fewer allocations
-		Iter::new(self).map(|(_i, a)| a)
-	}
 }
 
 /// Iterator that walks the binding tree in a DFS, yielding each chord's actions before deeper chords
@@ -247,9 +454,7 @@ impl<'a, A: Debug> Iterator for Iter<'a, A> {
 	fn next(&mut self) -> Option<Self::Item> {
 		loop {
 			let Some(current) = &mut self.current else {
-				let Some((input, bindings)) = self.queue.pop() else {
-					return None;
-				};
+				let (input, bindings) = self.queue.pop()?;
 				self.buf.extend(input);
 				self.current = Some(bindings.0.iter());
 				continue;
@@ -284,14 +489,16 @@ mod test {
 
 	fn example() -> Bindings<usize> {
 		let mut b = Bindings::empty();
-		b.insert(Input(KeyCode::Char('a'), KeyModifiers::NONE), 1);
+		b.insert(Input(KeyCode::Char('a'), KeyModifiers::NONE), 1)
+			.unwrap();
 		b.insert_chorded(
 			&[
 				Input(KeyCode::Char('b'), KeyModifiers::NONE),
 				Input(KeyCode::Char('c'), KeyModifiers::NONE),
 			],
 			2,
-		);
+		)
+		.unwrap();
 		b.insert_chorded(
 			&[
 				Input(KeyCode::Char('b'), KeyModifiers::NONE),
@@ -299,7 +506,8 @@ mod test {
 				Input(KeyCode::Char('e'), KeyModifiers::NONE),
 			],
 			3,
-		);
+		)
+		.unwrap();
 
 		b
 	}
@@ -380,4 +588,63 @@ mod test {
 		let actions_set: HashSet<_, RandomState> = HashSet::from_iter(actions);
 		assert_eq!(expected_set, actions_set)
 	}
+
+	#[test]
+	fn overrides_and_unbinds() {
+		let key = |c| Input(KeyCode::Char(c), KeyModifiers::NONE);
+		let mut b = example();
+
+		// an action replaces a chord and vice versa
+		assert!(b.bind(&[key('b')], 4).is_some());
+		assert!(b.bind(&[key('a'), key('f')], 5).is_some());
+		assert!(b.name_chord(&[key('a')], "A").is_none());
+		assert_eq!(Some(&4), b.get_single(key('b')));
+		assert!(matches!(
+			b.get(&[key('a')]),
+			Some(BindNode::Chord { name, .. }) if name == "A"
+		));
+		assert_eq!(
+			Some(&5),
+			b.get(&[key('a'), key('f')]).and_then(BindNode::action)
+		);
+
+		// removing the last binding of a chord removes it
+		assert!(b.unbind(&[key('a'), key('f')]).is_some());
+		assert!(b.get(&[key('a')]).is_none());
+		assert!(b.unbind(&[key('z')]).is_none());
+	}
+
+	#[test]
+	fn reports_conflicts() {
+		let key = |c| Input(KeyCode::Char(c), KeyModifiers::NONE);
+		let keys = |s: &str| s.parse::<InputBuffer>().unwrap();
+		let mut b = example();
+
+		let conflict = b.insert(key('a'), 4).unwrap_err();
+		assert_eq!(keys("a"), conflict.keys);
+		assert_eq!(Some(4), conflict.action);
+		assert_eq!(vec![(keys("a"), 1)], conflict.replaced);
+		let conflict = b.insert_chorded(&[key('a'), key('b')], 4).unwrap_err();
+		assert_eq!(vec![(keys("a"), 1)], conflict.replaced);
+		assert!(b.create_chord("A", &[key('a')]).is_err());
+		// nothing changed
+		assert_eq!(Some(&1), b.get_single(key('a')));
+
+		let mut replaced = b.bind(&[key('b')], 4).unwrap().replaced;
+		replaced.sort();
+		assert_eq!(vec![(keys("b c"), 2), (keys("b d e"), 3)], replaced);
+		assert_eq!(
+			"b (5) replaces b (4)",
+			b.bind(&[key('b')], 5).unwrap().to_string()
+		);
+		assert_eq!(
+			"Chord b replaces b (5)",
+			b.name_chord(&[key('b')], "B").unwrap().to_string()
+		);
+	}
+
+	#[test]
+	fn defaults_dont_conflict() {
+		Bindings::<Action>::defaults().unwrap();
+	}
 }
@@ -0,0 +1,20 @@
+//! Access to the system clipboard.
+use std::{io, sync::Mutex};
+
+use once_cell::sync::OnceCell;
+
+/// Kept alive for the life of the program, since on some platforms the copied
+/// contents are only available while the clipboard is open.
+static CLIPBOARD: OnceCell<Mutex<arboard::Clipboard>> = OnceCell::new();
+
+fn to_io(e: arboard::Error) -> io::Error {
+	io::Error::other(e.to_string())
+}
+
+pub fn copy(text: String) -> io::Result<()> {
+	let clipboard = CLIPBOARD
+		.get_or_try_init(|| arboard::Clipboard::new().map(Mutex::new))
+		.map_err(to_io)?;
+	let mut clipboard = clipboard.lock().unwrap_or_else(|e| e.into_inner());
+	clipboard.set_text(text).map_err(to_io)
+}
@@ -0,0 +1,141 @@
+//! Transparent (de)compression of files based on their extension and contents.
+use std::{
+	fs::File,
+	io::{self, Read, Write},
+	path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Compression {
+	#[default]
+	None,
+	Gzip,
+	Zstd,
+	Xz,
+}
+
+impl Compression {
+	const MAGIC: [(Self, &'static [u8]); 3] = [
+		(Self::Gzip, &[0x1f, 0x8b]),
+		(Self::Zstd, &[0x28, 0xb5, 0x2f, 0xfd]),
+		(Self::Xz, &[0xfd, b'7', b'z', b'X', b'Z', 0x00]),
+	];
+
+	pub fn from_extension(path: &Path) -> Self {
+		match path.extension().and_then(|e| e.to_str()) {
+			Some("gz" | "gzip") => Self::Gzip,
+			Some("zst" | "zstd") => Self::Zstd,
+			Some("xz") => Self::Xz,
+			_ => Self::None,
+		}
+	}
+
+	/// Detect compression from the first bytes of a file.
+	pub fn from_magic(bytes: &[u8]) -> Option<Self> {
+		Self::MAGIC
+			.iter()
+			.find(|(_c, magic)| bytes.starts_with(magic))
+			.map(|(c, _magic)| *c)
+	}
+
+	/// Detect compression of an existing file, falling back to its extension.
+	pub fn detect(path: &Path) -> io::Result<Self> {
+		let mut magic = Vec::with_capacity(6);
+		File::open(path)?.take(6).read_to_end(&mut magic)?;
+		Ok(Self::from_magic(&magic).unwrap_or_else(|| Self::from_extension(path)))
+	}
+
+	pub fn decoder<'r>(self, r: impl Read + 'r) -> io::Result<Box<dyn Read + 'r>> {
+		Ok(match self {
+			Self::None => Box::new(r),
+			Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(r)),
+			Self::Zstd => Box::new(zstd::Decoder::new(r)?),
+			Self::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(r)),
+		})
+	}
+
+	pub fn encoder<W: Write>(self, w: W) -> io::Result<Encoder<W>> {
+		Ok(match self {
+			Self::None => Encoder::None(w),
+			Self::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+				w,
+				flate2::Compression::default(),
+			)),
+			Self::Zstd => Encoder::Zstd(zstd::Encoder::new(w, 0)?),
+			Self::Xz => Encoder::Xz(xz2::write::XzEncoder::new(w, 6)),
+		})
+	}
+}
+
+/// Compressing writer that must be explicitly finished to write trailing data.
+pub enum Encoder<W: Write> {
+	None(W),
+	Gzip(flate2::write::GzEncoder<W>),
+	Zstd(zstd::Encoder<'static, W>),
+	Xz(xz2::write::XzEncoder<W>),
+}
+
+impl<W: Write> Encoder<W> {
+	/// Flush all compressed data and return the underlying writer.
+	pub fn finish(self) -> io::Result<W> {
+		let mut w = match self {
+			Self::None(w) => w,
+			Self::Gzip(e) => e.finish()?,
+			Self::Zstd(e) => e.finish()?,
+			Self::Xz(e) => e.finish()?,
+		};
+		w.flush()?;
+		Ok(w)
+	}
+
+	fn inner(&mut self) -> &mut dyn Write {
+		match self {
+			Self::None(w) => w,
+			Self::Gzip(e) => e,
+			Self::Zstd(e) => e,
+			Self::Xz(e) => e,
+		}
+	}
+}
+
+impl<W: Write> Write for Encoder<W> {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.inner().write(buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.inner().flush()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn roundtrips() {
+		let data = b"a,b\nc,d\n";
+		for c in [
+			Compression::None,
+			Compression::Gzip,
+			Compression::Zstd,
+			Compression::Xz,
+		] {
+			let mut e = c.encoder(Vec::new()).unwrap();
+			e.write_all(data).unwrap();
+			let compressed = e.finish().unwrap();
+			if c != Compression::None {
+				assert_eq!(Some(c), Compression::from_magic(&compressed));
+			}
+
+			let mut decompressed = Vec::new();
+			c.decoder(compressed.as_slice())
+				.unwrap()
+				.read_to_end(&mut decompressed)
+				.unwrap();
+			assert_eq!(data.as_slice(), decompressed);
+		}
+	}
+}
@@ -0,0 +1,284 @@
+//! User settings, read from `config.toml` in the config directory.
+//!
+//! Key bindings, written as described in [`crate::input`], are merged over the defaults:
+//!
+//! ```toml
+//! # bind a key, replacing what it was bound to
+//! [[bind]]
+//! keys = "<C-q>"
+//! action = "Quit"
+//!
+//! # leave out the action to unbind a key
+//! [[bind]]
+//! keys = "<C-c>"
+//!
+//! # bind a sequence of keys, making a chord of the first
+//! [[bind]]
+//! keys = "g g"
+//! action = "Home"
+//!
+//! # name the chord started by some keys
+//! [[chord]]
+//! keys = "g"
+//! name = "Go to"
+//! ```
+//!
+//! Setting `vim = true` starts with vim-style bindings, which the config's bindings are merged
+//! over too. Setting `chord_timeout = 1000` drops a partly typed chord after a second without the
+//! next key, rather than waiting for it forever.
+//!
+//! Recorded macros are kept in `macros.toml` beside it.
+use std::{
+	collections::BTreeMap,
+	env, fs, io,
+	path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+	bindings::{Bindings, Conflict},
+	input::InputBuffer,
+	program::Action,
+};
+
+/// Directory of the config file, `$XDG_CONFIG_HOME/sht` or `~/.config/sht`
+pub fn dir() -> Option<PathBuf> {
+	let base = match env::var_os("XDG_CONFIG_HOME") {
+		Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+		_ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+	};
+	Some(base.join("sht"))
+}
+
+pub fn path() -> Option<PathBuf> {
+	Some(dir()?.join("config.toml"))
+}
+
+/// Keys recorded into registers named by a character
+pub type Macros = BTreeMap<char, InputBuffer>;
+
+/// File macros are kept in, mapping registers to keys like `a = "<End> x j"`
+pub fn macros_path() -> Option<PathBuf> {
+	Some(dir()?.join("macros.toml"))
+}
+
+/// Read the macros at `path`, which are empty if it doesn't exist.
+pub fn load_macros(path: &Path) -> io::Result<Macros> {
+	let text = match fs::read_to_string(path) {
+		Ok(text) => text,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Macros::new()),
+		Err(e) => return Err(e),
+	};
+	let named: BTreeMap<String, InputBuffer> =
+		toml::from_str(&text).map_err(|e| invalid(e.message().to_string()))?;
+	named
+		.into_iter()
+		.map(|(name, keys)| {
+			let mut chars = name.chars();
+			match (chars.next(), chars.next()) {
+				(Some(c), None) => Ok((c, keys)),
+				_ => Err(invalid(format!(
+					"register `{name}` isn't a single character"
+				))),
+			}
+		})
+		.collect()
+}
+
+pub fn save_macros(path: &Path, macros: &Macros) -> io::Result<()> {
+	let named: BTreeMap<String, &InputBuffer> = macros
+		.iter()
+		.map(|(c, keys)| (c.to_string(), keys))
+		.collect();
+	let text = toml::to_string(&named).map_err(|e| invalid(e.to_string()))?;
+	if let Some(dir) = path.parent() {
+		fs::create_dir_all(dir)?;
+	}
+	fs::write(path, text)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+	/// Start with vim-style key bindings
+	#[serde(default)]
+	pub vim: bool,
+	/// Milliseconds to wait for the next key of a chord before dropping it, instead of forever
+	pub chord_timeout: Option<u64>,
+	/// Keys to bind or unbind, in order
+	#[serde(default)]
+	bind: Vec<Bind>,
+	/// Names of chords
+	#[serde(default)]
+	chord: Vec<Chord>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Bind {
+	keys: InputBuffer,
+	/// Action to bind to, or none to unbind
+	action: Option<Action>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Chord {
+	keys: InputBuffer,
+	name: String,
+}
+
+impl Config {
+	/// Read the config file at `path`, which is empty if it doesn't exist.
+	pub fn load(path: &Path) -> io::Result<Self> {
+		let text = match fs::read_to_string(path) {
+			Ok(text) => text,
+			Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+			Err(e) => return Err(e),
+		};
+		Self::parse(&text)
+	}
+
+	pub fn parse(text: &str) -> io::Result<Self> {
+		let config: Self = toml::from_str(text).map_err(|e| {
+			let line = e
+				.span()
+				.map_or(1, |span| text[..span.start].matches('\n').count() + 1);
+			invalid(format!("line {line}: {}", e.message()))
+		})?;
+		if config.bind.iter().any(|b| b.keys.is_empty()) {
+			return Err(invalid("a binding has no keys".to_string()));
+		}
+		if config.chord.iter().any(|c| c.keys.is_empty()) {
+			return Err(invalid("a chord has no keys".to_string()));
+		}
+		Ok(config)
+	}
+
+	/// Merge the config's bindings over `bindings`, returning the bindings they replaced.
+	pub fn apply(&self, bindings: &mut Bindings<Action>) -> Vec<Conflict<Action>> {
+		let mut conflicts = Vec::new();
+		for Bind { keys, action } in &self.bind {
+			match action {
+				Some(action) => conflicts.extend(bindings.bind(keys, *action)),
+				None => {
+					if bindings.unbind(keys).is_none() {
+						warn!("Unbinding {keys}, which isn't bound");
+					}
+				}
+			}
+		}
+		for Chord { keys, name } in &self.chord {
+			conflicts.extend(bindings.name_chord(keys, name));
+		}
+		for conflict in &conflicts {
+			warn!("{conflict}");
+		}
+		conflicts
+	}
+}
+
+fn invalid(msg: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod test {
+	use crossterm::event::{KeyCode, KeyModifiers};
+
+	use super::*;
+	use crate::{bindings::BindNode, input::Input};
+
+	#[test]
+	fn merges_over_defaults() {
+		let config = Config::parse(
+			r#"
+			[[bind]]
+			keys = "<C-q>"
+			action = "Quit"
+
+			[[bind]]
+			keys = "<C-c>"
+
+			[[bind]]
+			keys = "<C-r>"
+			action = "Quit"
+
+			[[bind]]
+			keys = "g g"
+			action = "Home"
+
+			[[bind]]
+			keys = "h h"
+			action = "HomeRow"
+
+			[[chord]]
+			keys = "g"
+			name = "Go to"
+			"#,
+		)
+		.unwrap();
+		let mut bindings = Bindings::default();
+		let conflicts = config.apply(&mut bindings);
+
+		let ctrl = |c| Input(KeyCode::Char(c), KeyModifiers::CONTROL);
+		let g = Input(KeyCode::Char('g'), KeyModifiers::NONE);
+		assert_eq!(Some(&Action::Quit), bindings.get_single(ctrl('q')));
+		assert_eq!(None, bindings.get_single(ctrl('c')));
+		assert_eq!(
+			Some(&Action::Home),
+			bindings.get(&[g, g]).and_then(BindNode::action)
+		);
+		assert!(matches!(
+			bindings.get(&[g]),
+			Some(BindNode::Chord { name, .. }) if name == "Go to"
+		));
+		assert_eq!(
+			vec![
+				"<C-r> (Quit) replaces <C-r> (Read)",
+				"h h (HomeRow) replaces h (Move(Left))"
+			],
+			conflicts
+				.iter()
+				.map(ToString::to_string)
+				.collect::<Vec<_>>()
+		);
+		// untouched defaults remain
+		assert_eq!(Some(&Action::Write), bindings.get_single(ctrl('s')));
+	}
+
+	#[test]
+	fn saves_macros() {
+		let path = env::temp_dir().join(format!("sht_macros_{}.toml", std::process::id()));
+		assert_eq!(Macros::new(), load_macros(&path).unwrap());
+		let macros = Macros::from([
+			('a', "<End> x j".parse().unwrap()),
+			('1', "g g".parse().unwrap()),
+		]);
+		save_macros(&path, &macros).unwrap();
+		assert_eq!(macros, load_macros(&path).unwrap());
+		fs::write(&path, "ab = \"x\"").unwrap();
+		let e = load_macros(&path).unwrap_err();
+		assert_eq!("register `ab` isn't a single character", e.to_string());
+		let _ = fs::remove_file(&path);
+	}
+
+	#[test]
+	fn reports_errors() {
+		let e = Config::parse("\n[[bind]]\nkeys = \"\"\naction = \"Quit\"").unwrap_err();
+		assert_eq!("a binding has no keys", e.to_string());
+		let e = Config::parse("\n[[bind]]\nkeys = \"a\"\naction = \"Nope\"").unwrap_err();
+		assert!(
+			e.to_string().starts_with("line 4: unknown variant `Nope`"),
+			"{e}"
+		);
+		let e = Config::parse("[[bind]]\nkeys = \"<C-x> <Foo>\"").unwrap_err();
+		assert_eq!("line 2: unknown key `<Foo>`", e.to_string());
+		let e = Config::parse("[[bind]]\nkey = \"a\"").unwrap_err();
+		assert!(
+			e.to_string().starts_with("line 2: unknown field `key`"),
+			"{e}"
+		);
+	}
+}
@@ -0,0 +1,277 @@
+//! Detection and conversion of text encodings other than UTF-8.
+use std::{fmt::Display, str::FromStr};
+
+use encoding_rs::{
+	DecoderResult, EncoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252,
+};
+use serde::{Deserialize, Serialize};
+
+/// Number of bytes sampled when guessing an encoding without a BOM
+const SAMPLE_LEN: usize = 4096;
+
+/// Text encoding of a file, detected on read and reused on write.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextEncoding {
+	encoding: &'static Encoding,
+	/// Whether the file starts with a byte order mark
+	bom: bool,
+}
+
+impl Default for TextEncoding {
+	fn default() -> Self {
+		Self {
+			encoding: UTF_8,
+			bom: false,
+		}
+	}
+}
+
+impl FromStr for TextEncoding {
+	type Err = String;
+
+	fn from_str(label: &str) -> Result<Self, Self::Err> {
+		let encoding = match label.to_ascii_lowercase().as_str() {
+			// encoding_rs treats this as an alias for utf-16le
+			"utf-16" | "utf16" => UTF_16LE,
+			label => Encoding::for_label(label.as_bytes())
+				.ok_or_else(|| format!("Unknown encoding: {label:?}"))?,
+		};
+		Ok(Self {
+			encoding,
+			bom: false,
+		})
+	}
+}
+
+impl Display for TextEncoding {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "{}", self.encoding.name())?;
+		if self.bom {
+			write!(f, " (BOM)")?;
+		}
+		Ok(())
+	}
+}
+
+/// Text decoded from bytes that may not have been entirely valid.
+#[derive(Debug)]
+pub struct Decoded {
+	pub text: String,
+	/// Line (starting at 1) of each sequence that couldn't be decoded and was replaced with
+	/// `U+FFFD`
+	pub errors: Vec<usize>,
+}
+
+impl TextEncoding {
+	/// Guess the encoding of `bytes`, using a BOM if present.
+	pub fn detect(bytes: &[u8]) -> Self {
+		if let Some((encoding, _len)) = Encoding::for_bom(bytes) {
+			return Self {
+				encoding,
+				bom: true,
+			};
+		}
+
+		let sample = &bytes[..bytes.len().min(SAMPLE_LEN)];
+		let valid = Encoding::utf8_valid_up_to(sample);
+		// the sample may end partway through a character
+		let is_truncated = sample.len() < bytes.len();
+		let is_utf8 = valid == sample.len() || (is_truncated && valid + 3 >= sample.len());
+		let encoding = if let Some(utf16) = guess_utf16(sample) {
+			utf16
+		} else if is_utf8 {
+			UTF_8
+		} else {
+			// superset of Latin-1 for printable characters
+			WINDOWS_1252
+		};
+
+		Self {
+			encoding,
+			bom: false,
+		}
+	}
+
+	pub fn name(&self) -> &'static str {
+		self.encoding.name()
+	}
+
+	/// Decode `bytes`, replacing any invalid sequences.
+	pub fn decode(&mut self, bytes: &[u8]) -> Decoded {
+		let mut bytes = bytes;
+		if let Some((encoding, len)) = Encoding::for_bom(bytes) {
+			if encoding == self.encoding {
+				self.bom = true;
+				bytes = &bytes[len..];
+			}
+		}
+
+		let mut decoder = self.encoding.new_decoder_without_bom_handling();
+		let mut text = String::with_capacity(
+			decoder
+				.max_utf8_buffer_length_without_replacement(bytes.len())
+				.unwrap_or(bytes.len()),
+		);
+		let mut errors = Vec::new();
+		// lines are counted in the decoded text, as newlines take more than a byte in some
+		// encodings, and other characters can contain their bytes
+		let (mut line, mut counted) = (1, 0);
+		let mut read = 0;
+		loop {
+			let (result, n) =
+				decoder.decode_to_string_without_replacement(&bytes[read..], &mut text, true);
+			read += n;
+			match result {
+				DecoderResult::InputEmpty => break,
+				DecoderResult::OutputFull => text.reserve(
+					decoder
+						.max_utf8_buffer_length_without_replacement(bytes.len() - read)
+						.unwrap_or(bytes.len() - read)
+						.max(4),
+				),
+				DecoderResult::Malformed(..) => {
+					line += text[counted..].matches('\n').count();
+					counted = text.len();
+					errors.push(line);
+					text.push(char::REPLACEMENT_CHARACTER);
+				}
+			}
+		}
+
+		Decoded { text, errors }
+	}
+
+	/// Encode `text`, returning the number of characters that couldn't be represented
+	/// and were replaced with `?`.
+	pub fn encode(&self, text: &str) -> (Vec<u8>, usize) {
+		let mut out = Vec::with_capacity(text.len());
+
+		// encoding_rs only decodes UTF-16
+		if self.encoding == UTF_16LE || self.encoding == UTF_16BE {
+			let be = self.encoding == UTF_16BE;
+			let units = self.bom.then_some(0xFEFF).into_iter();
+			for unit in units.chain(text.encode_utf16()) {
+				out.extend(if be {
+					unit.to_be_bytes()
+				} else {
+					unit.to_le_bytes()
+				});
+			}
+			return (out, 0);
+		}
+
+		if self.bom && self.encoding == UTF_8 {
+			out.extend(b"\xEF\xBB\xBF");
+		}
+
+		let mut encoder = self.encoding.new_encoder();
+		let mut unmappable = 0;
+		let mut read = 0;
+		loop {
+			let (result, n) =
+				encoder.encode_from_utf8_to_vec_without_replacement(&text[read..], &mut out, true);
+			read += n;
+			match result {
+				EncoderResult::InputEmpty => break,
+				EncoderResult::OutputFull => out.reserve(text.len() - read + 16),
+				EncoderResult::Unmappable(_c) => {
+					unmappable += 1;
+					out.push(b'?');
+				}
+			}
+		}
+
+		(out, unmappable)
+	}
+}
+
+/// Recognize UTF-16 without a BOM by the zero high bytes of ASCII characters.
+fn guess_utf16(sample: &[u8]) -> Option<&'static Encoding> {
+	let pairs = sample.len() / 2;
+	if pairs == 0 {
+		return None;
+	}
+	let (mut even, mut odd) = (0, 0);
+	for pair in sample.chunks_exact(2) {
+		if pair[0] == 0 {
+			even += 1;
+		}
+		if pair[1] == 0 {
+			odd += 1;
+		}
+	}
+	let threshold = pairs / 2;
+	match (even > threshold, odd > threshold) {
+		(false, true) => Some(UTF_16LE),
+		(true, false) => Some(UTF_16BE),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn roundtrip(encoding: TextEncoding, text: &str) {
+		let (bytes, unmappable) = encoding.encode(text);
+		assert_eq!(0, unmappable);
+		let mut detected = TextEncoding::detect(&bytes);
+		assert_eq!(encoding, detected);
+		let decoded = detected.decode(&bytes);
+		assert!(decoded.errors.is_empty());
+		assert_eq!(text, decoded.text);
+	}
+
+	#[test]
+	fn detects_and_roundtrips() {
+		let text = "name,city\nJosé,Zürich\n";
+		roundtrip(TextEncoding::default(), text);
+		roundtrip(
+			TextEncoding {
+				encoding: UTF_8,
+				bom: true,
+			},
+			text,
+		);
+		roundtrip(
+			TextEncoding {
+				encoding: WINDOWS_1252,
+				bom: false,
+			},
+			text,
+		);
+		for encoding in [UTF_16LE, UTF_16BE] {
+			for bom in [true, false] {
+				roundtrip(TextEncoding { encoding, bom }, text);
+			}
+		}
+	}
+
+	#[test]
+	fn reports_undecodable_bytes() {
+		let mut encoding: TextEncoding = "utf-8".parse().unwrap();
+		let bytes = b"a,b\nc,\xFF\n";
+		let decoded = encoding.decode(bytes);
+		assert_eq!("a,b\nc,\u{FFFD}\n", decoded.text);
+		assert_eq!(vec![2], decoded.errors);
+	}
+
+	#[test]
+	fn reports_lines_of_undecodable_utf16() {
+		let mut encoding: TextEncoding = "utf-16le".parse().unwrap();
+		// U+0A41 holds the byte of a newline, and a lone surrogate can't be decoded
+		let mut bytes = Vec::new();
+		for unit in [0x0A41, 0x0A, 0x61, 0x0A, 0xD800, 0x0A, 0xD800] {
+			bytes.extend(u16::to_le_bytes(unit));
+		}
+		let decoded = encoding.decode(&bytes);
+		assert_eq!("\u{0A41}\na\n\u{FFFD}\n\u{FFFD}", decoded.text);
+		assert_eq!(vec![3, 4], decoded.errors);
+	}
+
+	#[test]
+	fn replaces_unmappable_characters() {
+		let encoding: TextEncoding = "latin1".parse().unwrap();
+		assert_eq!((b"a?b".to_vec(), 1), encoding.encode("a\u{4E2D}b"));
+	}
+}
@@ -0,0 +1,125 @@
+//! File formats that can be loaded into and saved from a `Grid`.
+use std::{
+	fmt::Display,
+	io,
+	path::{Path, PathBuf},
+	str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{compression::Compression, grid::Grid};
+
+pub mod fixed;
+pub mod json;
+mod markup;
+pub use markup::TableFormat;
+pub mod spreadsheet;
+pub mod sql;
+
+/// Cells selected for exporting, with a header row
+#[derive(Debug)]
+pub struct Excerpt<'a> {
+	/// Name of the table, for formats that need one
+	pub name: &'a str,
+	pub header: Vec<&'a str>,
+	pub rows: Vec<Vec<&'a str>>,
+}
+
+/// Split a path like `file.ext:name` into the file and the name within it, if the file has one
+/// of `extensions`.
+fn split_path<'a>(path: &'a str, extensions: &[&str]) -> Option<(PathBuf, Option<&'a str>)> {
+	let matches = |p: &str| {
+		Path::new(p)
+			.extension()
+			.and_then(|e| e.to_str())
+			.is_some_and(|e| extensions.contains(&e.to_ascii_lowercase().as_str()))
+	};
+	if let Some((file, name)) = path.rsplit_once(':') {
+		if matches(file) && !name.is_empty() {
+			return Some((PathBuf::from(file), Some(name)));
+		}
+	}
+	matches(path).then(|| (PathBuf::from(path), None))
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Format {
+	#[default]
+	Csv,
+	/// Array of objects
+	Json,
+	/// One object per line
+	Ndjson,
+	/// Columns of fixed widths, given or inferred from a ruler line
+	Fixed,
+}
+
+impl FromStr for Format {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Ok(match s.to_ascii_lowercase().as_str() {
+			"csv" => Self::Csv,
+			"json" => Self::Json,
+			"ndjson" | "jsonl" => Self::Ndjson,
+			"fixed" | "fwf" => Self::Fixed,
+			_ => return Err(format!("Unknown format: {s:?}")),
+		})
+	}
+}
+
+impl Display for Format {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			Self::Csv => "CSV",
+			Self::Json => "JSON",
+			Self::Ndjson => "NDJSON",
+			Self::Fixed => "fixed-width",
+		};
+		write!(f, "{name}")
+	}
+}
+
+impl Format {
+	/// Guess the format from a file's extension, ignoring any compression extension.
+	pub fn from_path(path: &Path) -> Self {
+		let path = match Compression::from_extension(path) {
+			Compression::None => path,
+			_ => path.file_stem().map_or(path, Path::new),
+		};
+		path.extension()
+			.and_then(|e| e.to_str())
+			.and_then(|e| e.parse().ok())
+			.unwrap_or_default()
+	}
+
+	pub fn read(self, text: &str) -> io::Result<Grid> {
+		match self {
+			Self::Csv => Grid::from_csv(
+				csv::ReaderBuilder::new()
+					.has_headers(false)
+					.from_reader(text.as_bytes()),
+			),
+			Self::Json => json::read(text).map(|(grid, _)| grid),
+			Self::Ndjson => json::read_lines(text).map(|(grid, _)| grid),
+			Self::Fixed => Ok(fixed::Layout::infer(text, None)?.read(text)),
+		}
+	}
+
+	/// Write `grid`, with cells of JSON files written as the `types` they were read as.
+	pub fn write(self, grid: &Grid, types: &json::Types) -> io::Result<String> {
+		let bytes = match self {
+			Self::Csv => {
+				let mut wtr = csv::Writer::from_writer(Vec::new());
+				grid.to_csv(&mut wtr)?;
+				wtr.into_inner()
+					.map_err(|e| io::Error::new(e.error().kind(), e.to_string()))?
+			}
+			Self::Json => json::write(grid, types)?,
+			Self::Ndjson => json::write_lines(grid, types)?,
+			Self::Fixed => fixed::Layout::fit(grid)?.write(grid)?.text.into_bytes(),
+		};
+		String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+	}
+}
@@ -0,0 +1,380 @@
+//! Fixed-width text files, where each column takes up the same range of characters on every line.
+//!
+//! Columns are either given as `start:length` pairs, or inferred from a ruler line of dashes under
+//! the header like this:
+//!
+//! ```text
+//! NAME     AGE CITY
+//! -------- --- ----------
+//! Ada       36 London
+//! ```
+use std::{
+	fmt::Display,
+	io::{self, ErrorKind},
+	str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{grid::Grid, XY};
+
+/// Number of lines to search for a ruler
+const RULER_SEARCH: usize = 10;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Column {
+	/// Offset of the first character, starting at 0
+	start: usize,
+	width: usize,
+	/// Whether cells are padded on the left instead of the right
+	right_aligned: bool,
+}
+
+/// Columns given as a list of 1-based `start:length` pairs, like `1:8,9:3`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Columns(Vec<Column>);
+
+impl FromStr for Columns {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut columns: Vec<Column> = Vec::new();
+		for pair in s.split(',') {
+			let parse = |n: &str| {
+				n.trim()
+					.parse::<usize>()
+					.ok()
+					.filter(|&n| n > 0)
+					.ok_or_else(|| format!("Expected a positive number, found {n:?}"))
+			};
+			let (start, width) = pair
+				.split_once(':')
+				.ok_or_else(|| format!("Expected start:length, found {pair:?}"))?;
+			let (start, width) = (parse(start)? - 1, parse(width)?);
+			if let Some(prev) = columns.last() {
+				if start < prev.start + prev.width {
+					return Err(format!("Column {pair:?} overlaps the one before it"));
+				}
+			}
+			columns.push(Column {
+				start,
+				width,
+				right_aligned: false,
+			});
+		}
+		Ok(Self(columns))
+	}
+}
+
+impl Display for Columns {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		for (i, c) in self.0.iter().enumerate() {
+			if i != 0 {
+				write!(f, ",")?;
+			}
+			write!(f, "{}:{}", c.start + 1, c.width)?;
+		}
+		Ok(())
+	}
+}
+
+/// A ruler line kept to be written back in the same place
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Ruler {
+	line: usize,
+	text: String,
+}
+
+/// How the columns of a file are laid out, found when it is read and reused to write it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Layout {
+	columns: Vec<Column>,
+	ruler: Option<Ruler>,
+	/// Whether values can spill into the space before the next column, and the last column
+	/// extends to the end of the line, as they do under a ruler
+	open_ended: bool,
+	crlf: bool,
+}
+
+/// Text of a grid written with a layout
+pub struct Written {
+	pub text: String,
+	/// Cells that were truncated to fit their columns
+	pub overflow: usize,
+	pub first_overflow: Option<XY<usize>>,
+}
+
+fn is_ruler(line: &str) -> bool {
+	line.contains(['-', '='])
+		&& line
+			.chars()
+			.all(|c| matches!(c, '-' | '=' | ' ' | '\t' | '\r'))
+}
+
+/// Characters `start..start + width` of a line, or to the end if `width` is `None`
+fn field(line: &[char], start: usize, width: Option<usize>) -> String {
+	let end = width.map_or(line.len(), |w| start + w).min(line.len());
+	line.get(start..end).unwrap_or_default().iter().collect()
+}
+
+impl Layout {
+	/// Use the given columns, or infer them from a ruler line near the start of the text.
+	pub fn infer(text: &str, columns: Option<&Columns>) -> io::Result<Self> {
+		let crlf = text.contains("\r\n");
+		let ruler = text
+			.lines()
+			.take(RULER_SEARCH)
+			.position(is_ruler)
+			.map(|line| Ruler {
+				line,
+				text: text.lines().nth(line).unwrap().trim_end().to_string(),
+			});
+
+		let mut layout =
+			match (columns, &ruler) {
+				(Some(Columns(columns)), _) => Self {
+					columns: columns.clone(),
+					ruler,
+					open_ended: false,
+					crlf,
+				},
+				(None, Some(r)) => {
+					let chars: Vec<_> = r.text.chars().collect();
+					let is_mark = |i: usize| !chars[i].is_whitespace();
+					let columns = (0..chars.len())
+						.filter(|&i| is_mark(i) && (i == 0 || !is_mark(i - 1)))
+						.map(|start| Column {
+							start,
+							width: (start..chars.len()).take_while(|&i| is_mark(i)).count(),
+							right_aligned: false,
+						})
+						.collect();
+					Self {
+						columns,
+						ruler,
+						open_ended: true,
+						crlf,
+					}
+				}
+				(None, None) => return Err(io::Error::new(
+					ErrorKind::InvalidInput,
+					"Fixed-width files need a ruler line of dashes under the header, or --columns",
+				)),
+			};
+		layout.infer_alignment(text);
+		Ok(layout)
+	}
+
+	/// Treat columns as right-aligned if every value in them is flush with the end.
+	fn infer_alignment(&mut self, text: &str) {
+		let lines: Vec<Vec<char>> = self.lines(text).map(|l| l.chars().collect()).collect();
+		for column in &mut self.columns {
+			let fields: Vec<_> = lines
+				.iter()
+				.map(|l| field(l, column.start, Some(column.width)))
+				.filter(|f| !f.trim().is_empty())
+				.collect();
+			column.right_aligned = fields.iter().any(|f| f.starts_with(' '))
+				&& fields
+					.iter()
+					.all(|f| f.chars().count() == column.width && !f.ends_with(' '));
+		}
+	}
+
+	/// Fit columns to the contents of a grid, with a ruler under the header.
+	pub fn fit(grid: &Grid) -> io::Result<Self> {
+		let mut widths = vec![1; grid.size().x];
+		for row in grid.rows() {
+			for (width, cell) in widths.iter_mut().zip(row?.iter()) {
+				*width = cell.chars().count().max(*width);
+			}
+		}
+		let mut start = 0;
+		let columns: Vec<_> = widths
+			.into_iter()
+			.map(|width| {
+				let column = Column {
+					start,
+					width,
+					right_aligned: false,
+				};
+				start += width + 1;
+				column
+			})
+			.collect();
+		let ruler = columns
+			.iter()
+			.map(|c| "-".repeat(c.width))
+			.collect::<Vec<_>>()
+			.join(" ");
+		Ok(Self {
+			columns,
+			ruler: Some(Ruler {
+				line: 1,
+				text: ruler,
+			}),
+			open_ended: true,
+			crlf: false,
+		})
+	}
+
+	/// Lines of the text that hold rows
+	fn lines<'a>(&self, text: &'a str) -> impl Iterator<Item = &'a str> {
+		let ruler = self.ruler.as_ref().map(|r| r.line);
+		text.lines()
+			.enumerate()
+			.filter(move |(i, _)| Some(*i) != ruler)
+			.map(|(_, l)| l)
+	}
+
+	/// Number of characters read for a column, or `None` to read to the end of the line
+	fn span(&self, col: usize) -> Option<usize> {
+		let column = self.columns[col];
+		if !self.open_ended {
+			return Some(column.width);
+		}
+		self.columns
+			.get(col + 1)
+			.map(|next| next.start - column.start)
+	}
+
+	/// Number of rows above the ruler, naming the columns
+	pub fn header_rows(&self) -> usize {
+		self.ruler.as_ref().map_or(0, |r| r.line)
+	}
+
+	pub fn read(&self, text: &str) -> Grid {
+		let rows = self
+			.lines(text)
+			.map(|line| {
+				let line: Vec<_> = line.chars().collect();
+				(0..self.columns.len())
+					.map(|x| {
+						let start = self.columns[x].start;
+						field(&line, start, self.span(x)).trim().to_string()
+					})
+					.collect()
+			})
+			.collect();
+		Grid::from_rows(rows)
+	}
+
+	/// Write each cell padded to the width of its column, truncating those that don't fit in
+	/// the characters read for it, so every cell reads back the same.
+	///
+	/// Under a ruler, trailing spaces are left off.
+	pub fn write(&self, grid: &Grid) -> io::Result<Written> {
+		let newline = if self.crlf { "\r\n" } else { "\n" };
+		let mut written = Written {
+			text: String::new(),
+			overflow: 0,
+			first_overflow: None,
+		};
+		for (y, row) in grid.rows().enumerate() {
+			let row = row?;
+			if let Some(ruler) = self.ruler.as_ref().filter(|r| r.line == y) {
+				written.text.push_str(&ruler.text);
+				written.text.push_str(newline);
+			}
+			let mut line = String::new();
+			let mut len = 0;
+			for (x, column) in self.columns.iter().enumerate() {
+				let cell = row.get(x).map_or("", String::as_str);
+				let count = cell.chars().count();
+				let span = self.span(x).unwrap_or(count);
+				if count > span {
+					written.overflow += 1;
+					written.first_overflow.get_or_insert(XY { x, y });
+				}
+				let cell: String = cell.chars().take(span).collect();
+				let pad = " ".repeat(column.width.saturating_sub(cell.chars().count()));
+
+				line.extend(std::iter::repeat_n(' ', column.start.saturating_sub(len)));
+				if column.right_aligned {
+					line.push_str(&pad);
+					line.push_str(&cell);
+				} else {
+					line.push_str(&cell);
+					line.push_str(&pad);
+				}
+				len = column.start + column.width.max(cell.chars().count());
+			}
+			if self.open_ended {
+				line.truncate(line.trim_end().len());
+			}
+			written.text.push_str(&line);
+			written.text.push_str(newline);
+		}
+		if let Some(ruler) = self.ruler.as_ref().filter(|r| r.line >= grid.size().y) {
+			written.text.push_str(&ruler.text);
+			written.text.push_str(newline);
+		}
+		Ok(written)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn infers_columns_from_ruler() {
+		let text = "\
+NAME     AGE CITY
+-------- --- ----------
+Ada       36 London
+Grace    105 New York City
+";
+		let layout = Layout::infer(text, None).unwrap();
+		let grid = layout.read(text);
+		assert_eq!(
+			vec![
+				vec!["NAME", "AGE", "CITY"],
+				vec!["Ada", "36", "London"],
+				vec!["Grace", "105", "New York City"]
+			],
+			grid.to_vec().unwrap()
+		);
+		let written = layout.write(&grid).unwrap();
+		assert_eq!(0, written.overflow);
+		assert_eq!(text, written.text);
+	}
+
+	#[test]
+	fn spills_into_the_gap_under_a_ruler() {
+		let text = "A  B\n-  -\n1  2\n3\n";
+		let layout = Layout::infer(text, None).unwrap();
+		let mut grid = layout.read(text);
+		let _ = grid.edit(XY { x: 0, y: 1 }, "abc".into());
+		let _ = grid.edit(XY { x: 0, y: 2 }, "long".into());
+
+		let written = layout.write(&grid).unwrap();
+		assert_eq!("A  B\n-  -\nabc2\nlon\n", written.text);
+		assert_eq!(Some(XY { x: 0, y: 2 }), written.first_overflow);
+		assert_eq!(
+			vec![vec!["A", "B"], vec!["abc", "2"], vec!["lon", ""]],
+			layout.read(&written.text).to_vec().unwrap()
+		);
+	}
+
+	#[test]
+	fn truncates_to_given_columns() {
+		let columns: Columns = "1:3,6:2".parse().unwrap();
+		assert_eq!("1:3,6:2", columns.to_string());
+		assert!("1:3,2:2".parse::<Columns>().is_err());
+
+		let text = "abc  12\r\nde   3 \r\n";
+		let layout = Layout::infer(text, Some(&columns)).unwrap();
+		let mut grid = layout.read(text);
+		assert_eq!(
+			vec![vec!["abc", "12"], vec!["de", "3"]],
+			grid.to_vec().unwrap()
+		);
+
+		let _ = grid.edit(XY { x: 0, y: 1 }, "long".into());
+		let written = layout.write(&grid).unwrap();
+		assert_eq!("abc  12\r\nlon  3 \r\n", written.text);
+		assert_eq!(1, written.overflow);
+		assert_eq!(Some(XY { x: 0, y: 1 }), written.first_overflow);
+	}
+}
@@ -0,0 +1,353 @@
+//! JSON arrays of objects and newline-delimited JSON, with the header row holding the keys.
+//!
+//! Nested objects and arrays are flattened into columns with dotted paths (`address.city`,
+//! `tags.0`) on read, and rebuilt from them on write. Cells are written back as the type they
+//! were read as, and cells that weren't read, like edited ones, as the type they look like.
+//! Keys that objects didn't have are left out again, unless other objects had them empty.
+use std::{
+	collections::HashMap,
+	io::{self, ErrorKind},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::grid::Grid;
+
+/// Values read from a file that wouldn't be written back as the same type, by column and text,
+/// found when it is read and reused to write it
+///
+/// `None` is kept for empty cells of keys that objects didn't have, in columns without empty
+/// values.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Types(HashMap<String, HashMap<String, Option<Value>>>);
+
+impl Types {
+	/// Remember that `text` in the column `key` was read from `value`, or from a missing key,
+	/// unless it would be written back as it is anyway, or it was read from another value before.
+	fn insert(&mut self, key: &str, text: &str, value: Option<Value>) {
+		if value.as_ref() == Some(&parse_cell(text)) {
+			return;
+		}
+		let column = self.0.entry(key.to_string()).or_default();
+		column.entry(text.to_string()).or_insert(value);
+	}
+
+	/// Value to write for `text` in the column `key`, or `None` to leave the key out
+	fn value(&self, key: &str, text: &str) -> Option<Value> {
+		match self.0.get(key).and_then(|column| column.get(text)) {
+			Some(value) => value.clone(),
+			None => Some(parse_cell(text)),
+		}
+	}
+}
+
+pub fn read(text: &str) -> io::Result<(Grid, Types)> {
+	let Value::Array(items) = serde_json::from_str(text)? else {
+		return Err(invalid("Expected an array of objects"));
+	};
+	from_objects(items)
+}
+
+pub fn read_lines(text: &str) -> io::Result<(Grid, Types)> {
+	let items = text
+		.lines()
+		.filter(|l| !l.trim().is_empty())
+		.map(serde_json::from_str)
+		.collect::<Result<Vec<Value>, _>>()?;
+	from_objects(items)
+}
+
+pub fn write(grid: &Grid, types: &Types) -> io::Result<Vec<u8>> {
+	let mut out = serde_json::to_vec_pretty(&to_objects(grid, types)?)?;
+	out.push(b'\n');
+	Ok(out)
+}
+
+pub fn write_lines(grid: &Grid, types: &Types) -> io::Result<Vec<u8>> {
+	let mut out = Vec::new();
+	for object in to_objects(grid, types)? {
+		serde_json::to_writer(&mut out, &object)?;
+		out.push(b'\n');
+	}
+	Ok(out)
+}
+
+fn invalid(msg: &str) -> io::Error {
+	io::Error::new(ErrorKind::InvalidData, msg)
+}
+
+fn from_objects(items: Vec<Value>) -> io::Result<(Grid, Types)> {
+	let mut header: Vec<String> = Vec::new();
+	let mut objects = Vec::with_capacity(items.len());
+	for item in items {
+		let Value::Object(object) = item else {
+			return Err(invalid("Expected only objects"));
+		};
+		let mut fields = Vec::new();
+		flatten(String::new(), Value::Object(object), &mut fields);
+		let fields: Vec<_> = fields
+			.into_iter()
+			.map(|(key, value)| match header.iter().position(|h| *h == key) {
+				Some(col) => (col, value),
+				None => {
+					header.push(key);
+					(header.len() - 1, value)
+				}
+			})
+			.collect();
+		objects.push(fields);
+	}
+
+	// empty cells are written as missing keys only in columns that had no empty values, as
+	// rows can be moved
+	let mut has_empty = vec![false; header.len()];
+	for &(col, ref value) in objects.iter().flatten() {
+		has_empty[col] |= cell_text(value).is_empty();
+	}
+	let mut types = Types::default();
+	let mut rows = Vec::with_capacity(objects.len());
+	for fields in objects {
+		let mut values = vec![None; header.len()];
+		for (col, value) in fields {
+			values[col] = Some(value);
+		}
+		let row = values
+			.into_iter()
+			.enumerate()
+			.map(|(col, value)| {
+				let text = value.as_ref().map(cell_text).unwrap_or_default();
+				if value.is_some() || !has_empty[col] {
+					types.insert(&header[col], &text, value);
+				}
+				text
+			})
+			.collect();
+		rows.push(row);
+	}
+
+	let mut cells = Vec::with_capacity(rows.len() + 1);
+	cells.push(header);
+	cells.extend(rows);
+	Ok((Grid::from_rows(cells), types))
+}
+
+/// Collect the leaf values of `value` with their dotted paths.
+fn flatten(path: String, value: Value, out: &mut Vec<(String, Value)>) {
+	let join = |key: &str| {
+		if path.is_empty() {
+			key.to_string()
+		} else {
+			format!("{path}.{key}")
+		}
+	};
+	match value {
+		Value::Object(o) if !o.is_empty() => {
+			for (key, value) in o {
+				flatten(join(&key), value, out);
+			}
+		}
+		Value::Array(a) if !a.is_empty() => {
+			for (i, value) in a.into_iter().enumerate() {
+				flatten(join(&i.to_string()), value, out);
+			}
+		}
+		leaf => out.push((path, leaf)),
+	}
+}
+
+/// Text of a cell holding a leaf value
+fn cell_text(value: &Value) -> String {
+	match value {
+		Value::Null => String::new(),
+		Value::String(s) => s.clone(),
+		other => other.to_string(),
+	}
+}
+
+fn to_objects(grid: &Grid, types: &Types) -> io::Result<Vec<Value>> {
+	let cells = grid.to_vec()?;
+	let Some((header, rows)) = cells.split_first() else {
+		return Ok(Vec::new());
+	};
+	Ok(rows
+		.iter()
+		.map(|row| {
+			let mut object = Map::new();
+			for (key, cell) in header.iter().zip(row) {
+				if let Some(value) = types.value(key, cell) {
+					insert_path(&mut object, key, value);
+				}
+			}
+			arrays_from_indices(Value::Object(object))
+		})
+		.collect())
+}
+
+/// Interpret a cell as the JSON value it most likely holds.
+fn parse_cell(cell: &str) -> Value {
+	match cell {
+		"" => Value::Null,
+		"true" => Value::Bool(true),
+		"false" => Value::Bool(false),
+		"{}" => Value::Object(Map::new()),
+		"[]" => Value::Array(Vec::new()),
+		_ => match serde_json::from_str::<serde_json::Number>(cell) {
+			// keep the original text of numbers that wouldn't survive parsing, like `007`
+			Ok(n) if n.to_string() == cell => Value::Number(n),
+			_ => Value::String(cell.to_string()),
+		},
+	}
+}
+
+/// Insert `value` at a dotted path, keeping the key as-is if it conflicts with another value.
+fn insert_path(object: &mut Map<String, Value>, key: &str, value: Value) {
+	let mut target = &mut *object;
+	let mut parts = key.split('.').peekable();
+	while let Some(part) = parts.next() {
+		if parts.peek().is_none() {
+			if target.contains_key(part) {
+				break;
+			}
+			target.insert(part.to_string(), value);
+			return;
+		}
+		let entry = target
+			.entry(part)
+			.or_insert_with(|| Value::Object(Map::new()));
+		match entry {
+			Value::Object(o) => target = o,
+			_ => break,
+		}
+	}
+	object.insert(key.to_string(), value);
+}
+
+/// Turn nested objects whose keys are exactly `0..n` back into arrays.
+fn arrays_from_indices(value: Value) -> Value {
+	let Value::Object(object) = value else {
+		return value;
+	};
+	let is_array =
+		!object.is_empty() && object.keys().enumerate().all(|(i, k)| *k == i.to_string());
+	if is_array {
+		Value::Array(
+			object
+				.into_iter()
+				.map(|(_k, v)| arrays_from_indices(v))
+				.collect(),
+		)
+	} else {
+		Value::Object(
+			object
+				.into_iter()
+				.map(|(k, v)| (k, arrays_from_indices(v)))
+				.collect(),
+		)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::XY;
+
+	#[test]
+	fn flattens_and_rebuilds_nested_values() {
+		let text = r#"[
+  {
+    "id": 1,
+    "name": "Ada",
+    "address": {
+      "city": "London",
+      "zip": "007"
+    },
+    "tags": [
+      "a",
+      "b"
+    ],
+    "active": true
+  },
+  {
+    "id": 2,
+    "name": null,
+    "address": {
+      "city": "Paris",
+      "zip": "75001"
+    },
+    "tags": [
+      "c",
+      "d"
+    ],
+    "active": false
+  }
+]
+"#;
+		let (grid, types) = read(text).unwrap();
+		assert_eq!(
+			&vec![
+				"id",
+				"name",
+				"address.city",
+				"address.zip",
+				"tags.0",
+				"tags.1",
+				"active"
+			],
+			&grid.to_vec().unwrap()[0]
+		);
+		assert_eq!(
+			&vec!["2", "", "Paris", "75001", "c", "d", "false"],
+			&grid.to_vec().unwrap()[2]
+		);
+
+		let written = String::from_utf8(write(&grid, &types).unwrap()).unwrap();
+		assert_eq!(text, written);
+	}
+
+	#[test]
+	fn keeps_types_of_values_read() {
+		let text = "{\"zip\":\"12345\",\"flag\":\"true\",\"note\":\"\",\"n\":1,\"id\":\"7\"}\n";
+		let (mut grid, types) = read_lines(text).unwrap();
+		let written = |grid: &Grid| String::from_utf8(write_lines(grid, &types).unwrap()).unwrap();
+		assert_eq!(text, written(&grid));
+
+		// edited cells are written as the type they look like, unless they were read in the column
+		let _ = grid.edit(XY { x: 2, y: 1 }, "2".into());
+		let _ = grid.edit(XY { x: 3, y: 1 }, "x".into());
+		let _ = grid.edit(XY { x: 4, y: 1 }, "7".into());
+		assert_eq!(
+			"{\"zip\":\"12345\",\"flag\":\"true\",\"note\":2,\"n\":\"x\",\"id\":\"7\"}\n",
+			written(&grid)
+		);
+	}
+
+	#[test]
+	fn reads_lines_with_differing_keys() {
+		let (mut grid, types) =
+			read_lines("{\"a\": 1}\n\n{\"b\": \"x\", \"a\": 2}\n{\"c\": null}\n").unwrap();
+		// c is written for every object, as which were missing it can't be told from which
+		// had it empty once rows are moved
+		assert_eq!(
+			vec![
+				vec!["a", "b", "c"],
+				vec!["1", "", ""],
+				vec!["2", "x", ""],
+				vec!["", "", ""]
+			],
+			grid.to_vec().unwrap()
+		);
+		let written = |grid: &Grid| String::from_utf8(write_lines(grid, &types).unwrap()).unwrap();
+		assert_eq!(
+			"{\"a\":1,\"c\":null}\n{\"a\":2,\"b\":\"x\",\"c\":null}\n{\"c\":null}\n",
+			written(&grid)
+		);
+
+		// filling in a missing key adds it
+		let _ = grid.edit(XY { x: 1, y: 1 }, "y".into());
+		assert_eq!(
+			"{\"a\":1,\"b\":\"y\",\"c\":null}\n{\"a\":2,\"b\":\"x\",\"c\":null}\n{\"c\":null}\n",
+			written(&grid)
+		);
+	}
+}
@@ -0,0 +1,276 @@
+//! Export-only table formats for pasting into documents and databases.
+use std::fmt::Write;
+
+use enum_iterator::Sequence;
+use serde::{Deserialize, Serialize};
+use unicode_width::UnicodeWidthStr;
+
+use super::{sql, Excerpt};
+
+#[derive(
+	Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Sequence, Serialize, Deserialize,
+)]
+pub enum TableFormat {
+	/// GitHub-flavored Markdown
+	Markdown,
+	Html,
+	/// LaTeX `tabular`
+	Latex,
+	/// SQL `CREATE TABLE` and `INSERT` statements
+	Sql,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Align {
+	Left,
+	Right,
+}
+
+impl TableFormat {
+	/// Name of the format, for display
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Markdown => "Markdown",
+			Self::Html => "HTML",
+			Self::Latex => "LaTeX",
+			Self::Sql => "SQL",
+		}
+	}
+
+	pub fn extension(self) -> &'static str {
+		match self {
+			Self::Markdown => "md",
+			Self::Html => "html",
+			Self::Latex => "tex",
+			Self::Sql => "sql",
+		}
+	}
+
+	/// Render a table with a header row, aligning columns of numbers to the right.
+	pub fn render(self, excerpt: &Excerpt) -> String {
+		let Excerpt { header, rows, .. } = excerpt;
+		let align = infer_alignment(header.len(), rows);
+		match self {
+			Self::Markdown => markdown(header, rows, &align),
+			Self::Html => html(header, rows, &align),
+			Self::Latex => latex(header, rows, &align),
+			Self::Sql => sql::statements(excerpt),
+		}
+	}
+}
+
+fn is_numeric(cell: &str) -> bool {
+	let cell = cell.trim();
+	let cell = cell.strip_prefix(['-', '+']).unwrap_or(cell);
+	let cell = cell.strip_suffix('%').unwrap_or(cell);
+	!cell.is_empty()
+		&& cell.chars().any(|c| c.is_ascii_digit())
+		&& cell
+			.chars()
+			.all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+}
+
+fn infer_alignment(width: usize, rows: &[Vec<&str>]) -> Vec<Align> {
+	(0..width)
+		.map(|col| {
+			let mut cells = rows
+				.iter()
+				.filter_map(|r| r.get(col))
+				.filter(|c| !c.trim().is_empty())
+				.peekable();
+			if cells.peek().is_some() && cells.all(|c| is_numeric(c)) {
+				Align::Right
+			} else {
+				Align::Left
+			}
+		})
+		.collect()
+}
+
+fn markdown(header: &[&str], rows: &[Vec<&str>], align: &[Align]) -> String {
+	fn escape(cell: &str) -> String {
+		cell.trim()
+			.replace('\\', "\\\\")
+			.replace('|', "\\|")
+			.replace("\r\n", "<br>")
+			.replace('\n', "<br>")
+	}
+
+	let header: Vec<_> = header.iter().map(|c| escape(c)).collect();
+	let rows: Vec<Vec<_>> = rows
+		.iter()
+		.map(|r| r.iter().map(|c| escape(c)).collect())
+		.collect();
+	let widths: Vec<_> = (0..header.len())
+		.map(|col| {
+			rows.iter()
+				.filter_map(|r| r.get(col))
+				.chain([&header[col]])
+				.map(|c| c.width())
+				.max()
+				.unwrap_or_default()
+				.max(3)
+		})
+		.collect();
+
+	let mut out = String::new();
+	let mut write_row = |cells: &[String]| {
+		out.push('|');
+		for (col, &width) in widths.iter().enumerate() {
+			let cell = cells.get(col).map(String::as_str).unwrap_or_default();
+			let pad = " ".repeat(width - cell.width());
+			match align[col] {
+				Align::Left => write!(out, " {cell}{pad} |").unwrap(),
+				Align::Right => write!(out, " {pad}{cell} |").unwrap(),
+			}
+		}
+		out.push('\n');
+	};
+	write_row(&header);
+	let rule: Vec<_> = widths
+		.iter()
+		.zip(align)
+		.map(|(&width, align)| match align {
+			Align::Left => "-".repeat(width),
+			Align::Right => format!("{}:", "-".repeat(width - 1)),
+		})
+		.collect();
+	write_row(&rule);
+	for row in &rows {
+		write_row(row);
+	}
+	out
+}
+
+fn html(header: &[&str], rows: &[Vec<&str>], align: &[Align]) -> String {
+	fn escape(cell: &str) -> String {
+		let mut out = String::with_capacity(cell.len());
+		for c in cell.chars() {
+			match c {
+				'&' => out.push_str("&amp;"),
+				'<' => out.push_str("&lt;"),
+				'>' => out.push_str("&gt;"),
+				'"' => out.push_str("&quot;"),
+				'\'' => out.push_str("&#39;"),
+				'\n' => out.push_str("<br>"),
+				c => out.push(c),
+			}
+		}
+		out
+	}
+
+	let write_row = |out: &mut String, tag: &str, cells: &[&str]| {
+		out.push_str("    <tr>\n");
+		for (col, align) in align.iter().enumerate() {
+			let cell = cells.get(col).copied().unwrap_or_default();
+			let style = match align {
+				Align::Left => "",
+				Align::Right => " style=\"text-align: right\"",
+			};
+			writeln!(out, "      <{tag}{style}>{}</{tag}>", escape(cell)).unwrap();
+		}
+		out.push_str("    </tr>\n");
+	};
+
+	let mut out = String::from("<table>\n  <thead>\n");
+	write_row(&mut out, "th", header);
+	out.push_str("  </thead>\n  <tbody>\n");
+	for row in rows {
+		write_row(&mut out, "td", row);
+	}
+	out.push_str("  </tbody>\n</table>\n");
+	out
+}
+
+fn latex(header: &[&str], rows: &[Vec<&str>], align: &[Align]) -> String {
+	fn escape(cell: &str) -> String {
+		let mut out = String::with_capacity(cell.len());
+		for c in cell.chars() {
+			match c {
+				'\\' => out.push_str("\\textbackslash{}"),
+				'~' => out.push_str("\\textasciitilde{}"),
+				'^' => out.push_str("\\textasciicircum{}"),
+				'&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+					out.push('\\');
+					out.push(c);
+				}
+				'\n' => out.push(' '),
+				c => out.push(c),
+			}
+		}
+		out
+	}
+
+	let write_row = |out: &mut String, cells: &[&str]| {
+		let cells: Vec<_> = (0..align.len())
+			.map(|col| escape(cells.get(col).copied().unwrap_or_default()))
+			.collect();
+		writeln!(out, "  {} \\\\", cells.join(" & ")).unwrap();
+	};
+
+	let spec: String = align
+		.iter()
+		.map(|a| match a {
+			Align::Left => 'l',
+			Align::Right => 'r',
+		})
+		.collect();
+	let mut out = format!("\\begin{{tabular}}{{{spec}}}\n  \\hline\n");
+	write_row(&mut out, header);
+	out.push_str("  \\hline\n");
+	for row in rows {
+		write_row(&mut out, row);
+	}
+	out.push_str("  \\hline\n\\end{tabular}\n");
+	out
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn example() -> Excerpt<'static> {
+		Excerpt {
+			name: "example",
+			header: vec!["name", "a|b & <c>", "count"],
+			rows: vec![vec!["x_1", "50%", "10"], vec!["y", "{z}", "-2.5"]],
+		}
+	}
+
+	#[test]
+	fn renders_markdown() {
+		assert_eq!(
+			"\
+| name | a\\|b & <c> | count |
+| ---- | ---------- | ----: |
+| x_1  | 50%        |    10 |
+| y    | {z}        |  -2.5 |
+",
+			TableFormat::Markdown.render(&example())
+		);
+	}
+
+	#[test]
+	fn renders_html() {
+		let html = TableFormat::Html.render(&example());
+		assert!(html.contains("<th>a|b &amp; &lt;c&gt;</th>"));
+		assert!(html.contains("<td style=\"text-align: right\">-2.5</td>"));
+	}
+
+	#[test]
+	fn renders_latex() {
+		assert_eq!(
+			"\
+\\begin{tabular}{llr}
+  \\hline
+  name & a|b \\& <c> & count \\\\
+  \\hline
+  x\\_1 & 50\\% & 10 \\\\
+  y & \\{z\\} & -2.5 \\\\
+  \\hline
+\\end{tabular}
+",
+			TableFormat::Latex.render(&example())
+		);
+	}
+}
@@ -0,0 +1,126 @@
+//! Read-only import of worksheets from XLSX, XLS, and ODS workbooks.
+use std::{
+	io::{self, ErrorKind},
+	path::{Path, PathBuf},
+};
+
+use calamine::{open_workbook_auto, Data, Reader};
+use serde::{Deserialize, Serialize};
+
+use crate::grid::Grid;
+
+/// Extensions of files treated as workbooks
+const EXTENSIONS: [&str; 5] = ["xlsx", "xlsm", "xlsb", "xls", "ods"];
+
+/// Split a path like `book.xlsx:Sheet` into the workbook and optional sheet name,
+/// if it refers to a workbook.
+pub fn workbook_path(path: &str) -> Option<(PathBuf, Option<&str>)> {
+	super::split_path(path, &EXTENSIONS)
+}
+
+fn to_io(e: calamine::Error) -> io::Error {
+	io::Error::new(ErrorKind::InvalidData, e.to_string())
+}
+
+/// A worksheet in a workbook on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Workbook {
+	path: PathBuf,
+	sheet: String,
+}
+
+impl Workbook {
+	/// Open the workbook at `path` to the named sheet, or the first one, returning it with
+	/// the names of all of its sheets.
+	pub fn open(path: &Path, sheet: Option<&str>) -> io::Result<(Self, Vec<String>)> {
+		let sheets = open_workbook_auto(path).map_err(to_io)?.sheet_names();
+		let sheet = match sheet {
+			Some(sheet) if sheets.iter().any(|s| s == sheet) => sheet.to_string(),
+			Some(sheet) => {
+				return Err(io::Error::new(
+					ErrorKind::NotFound,
+					format!("No sheet {sheet:?} in {path:?}"),
+				))
+			}
+			None => sheets.first().cloned().ok_or_else(|| {
+				io::Error::new(ErrorKind::InvalidData, format!("No sheets in {path:?}"))
+			})?,
+		};
+		let workbook = Self {
+			path: path.to_path_buf(),
+			sheet,
+		};
+		Ok((workbook, sheets))
+	}
+
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+
+	pub fn sheet(&self) -> &str {
+		&self.sheet
+	}
+
+	pub fn set_sheet(&mut self, sheet: String) {
+		self.sheet = sheet;
+	}
+
+	/// Read the cached values of the sheet, keeping cells at the same positions as in the workbook.
+	pub fn read(&self) -> io::Result<Grid> {
+		let range = open_workbook_auto(&self.path)
+			.and_then(|mut book| book.worksheet_range(&self.sheet))
+			.map_err(to_io)?;
+		let (top, left) = range.start().unwrap_or_default();
+		let mut cells = vec![Vec::new(); top as usize];
+		cells.extend(range.rows().map(|row| {
+			let mut cells = vec![String::new(); left as usize];
+			cells.extend(row.iter().map(cell));
+			cells
+		}));
+		Ok(Grid::from_rows(cells))
+	}
+}
+
+fn cell(data: &Data) -> String {
+	match data {
+		Data::DateTime(dt) if dt.is_duration() => {
+			let seconds = (dt.as_f64() * 86400.0).round() as i64;
+			let (h, m, s) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+			format!("{h}:{m:02}:{s:02}")
+		}
+		Data::DateTime(dt) => {
+			let (y, mo, d, h, mi, s, _ms) = dt.to_ymd_hms_milli();
+			let date = format!("{y:04}-{mo:02}-{d:02}");
+			let time = format!("{h:02}:{mi:02}:{s:02}");
+			if dt.as_f64() < 1.0 {
+				time
+			} else if (h, mi, s) == (0, 0, 0) {
+				date
+			} else {
+				format!("{date} {time}")
+			}
+		}
+		other => other.to_string(),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use calamine::{ExcelDateTime, ExcelDateTimeType};
+
+	use super::*;
+
+	#[test]
+	fn formats_cells() {
+		let date = |value, kind| cell(&Data::DateTime(ExcelDateTime::new(value, kind, false)));
+		assert_eq!("2024-03-01", date(45352.0, ExcelDateTimeType::DateTime));
+		assert_eq!(
+			"2024-03-01 12:00:00",
+			date(45352.5, ExcelDateTimeType::DateTime)
+		);
+		assert_eq!("06:00:00", date(0.25, ExcelDateTimeType::DateTime));
+		assert_eq!("36:30:00", date(1.5208333, ExcelDateTimeType::TimeDelta));
+		assert_eq!("1.5", cell(&Data::Float(1.5)));
+		assert_eq!("", cell(&Data::Empty));
+	}
+}
@@ -0,0 +1,687 @@
+//! SQL statements and SQLite databases.
+use std::{
+	collections::BTreeSet,
+	fmt::Write,
+	io::{self, ErrorKind},
+	path::{Path, PathBuf},
+};
+
+use rusqlite::{
+	types::{Value, ValueRef},
+	Connection, OptionalExtension,
+};
+use serde::{Deserialize, Serialize};
+
+use super::Excerpt;
+use crate::{
+	grid::{Change, Grid},
+	XY,
+};
+
+/// Extensions of files treated as SQLite databases
+const EXTENSIONS: [&str; 3] = ["db", "sqlite", "sqlite3"];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SqlType {
+	Integer,
+	Real,
+	Text,
+}
+
+impl SqlType {
+	fn name(self) -> &'static str {
+		match self {
+			Self::Integer => "INTEGER",
+			Self::Real => "REAL",
+			Self::Text => "TEXT",
+		}
+	}
+
+	/// Type SQLite stores values as in a column declared as `declared`, by its rules for type
+	/// affinity, or `None` for numeric columns, which store each as the type it fits
+	fn affinity(declared: &str) -> Option<Self> {
+		let declared = declared.to_ascii_uppercase();
+		let has = |names: &[&str]| names.iter().any(|n| declared.contains(n));
+		if has(&["INT"]) {
+			Some(Self::Integer)
+		} else if has(&["CHAR", "CLOB", "TEXT", "BLOB", "ANY"]) || declared.is_empty() {
+			// text is stored as text in blob and untyped columns too
+			Some(Self::Text)
+		} else if has(&["REAL", "FLOA", "DOUB"]) {
+			Some(Self::Real)
+		} else {
+			None
+		}
+	}
+
+	/// Narrowest type that can hold every non-empty cell
+	fn infer<'a>(cells: impl IntoIterator<Item = &'a str>) -> Self {
+		cells
+			.into_iter()
+			.filter(|c| !c.is_empty())
+			.fold(Self::Integer, |t, cell| match t {
+				Self::Integer if cell.parse::<i64>().is_ok() => Self::Integer,
+				Self::Integer | Self::Real if cell.parse::<f64>().is_ok_and(f64::is_finite) => {
+					Self::Real
+				}
+				_ => Self::Text,
+			})
+	}
+
+	/// Value of a cell in a column of this type, with empty cells as `NULL`.
+	fn value(self, cell: &str) -> Value {
+		if cell.is_empty() {
+			return Value::Null;
+		}
+		match self {
+			Self::Integer => cell.parse().map(Value::Integer).map_err(|_| ()),
+			Self::Real => cell.parse().map(Value::Real).map_err(|_| ()),
+			Self::Text => return Value::Text(cell.to_string()),
+		}
+		.unwrap_or_else(|_| Value::Text(cell.to_string()))
+	}
+}
+
+/// Split a path like `data.db:table` into the database and optional table name,
+/// if it refers to a SQLite database.
+pub fn database_path(path: &str) -> Option<(PathBuf, Option<&str>)> {
+	super::split_path(path, &EXTENSIONS)
+}
+
+fn to_io(e: rusqlite::Error) -> io::Error {
+	io::Error::other(e.to_string())
+}
+
+pub fn quote_ident(ident: &str) -> String {
+	format!("\"{}\"", ident.replace('"', "\"\""))
+}
+
+fn literal(value: &Value) -> String {
+	match value {
+		Value::Null => "NULL".to_string(),
+		Value::Integer(i) => i.to_string(),
+		Value::Real(f) => f.to_string(),
+		Value::Text(s) => format!("'{}'", s.replace('\'', "''")),
+		Value::Blob(b) => format!(
+			"X'{}'",
+			b.iter().map(|b| format!("{b:02X}")).collect::<String>()
+		),
+	}
+}
+
+fn column_types(excerpt: &Excerpt) -> Vec<SqlType> {
+	(0..excerpt.header.len())
+		.map(|col| SqlType::infer(excerpt.rows.iter().filter_map(|r| r.get(col).copied())))
+		.collect()
+}
+
+fn create_table(excerpt: &Excerpt, types: &[SqlType]) -> String {
+	let columns: Vec<_> = excerpt
+		.header
+		.iter()
+		.zip(types)
+		.map(|(name, t)| format!("{} {}", quote_ident(name), t.name()))
+		.collect();
+	format!(
+		"CREATE TABLE {} ({})",
+		quote_ident(excerpt.name),
+		columns.join(", ")
+	)
+}
+
+fn insert_into(excerpt: &Excerpt) -> String {
+	let columns: Vec<_> = excerpt.header.iter().map(|c| quote_ident(c)).collect();
+	format!(
+		"INSERT INTO {} ({}) VALUES",
+		quote_ident(excerpt.name),
+		columns.join(", ")
+	)
+}
+
+/// `CREATE TABLE` and `INSERT` statements for the excerpt, with inferred column types.
+pub fn statements(excerpt: &Excerpt) -> String {
+	let types = column_types(excerpt);
+	let insert = insert_into(excerpt);
+	let mut out = String::from("BEGIN TRANSACTION;\n");
+	writeln!(out, "{};", create_table(excerpt, &types)).unwrap();
+	for row in &excerpt.rows {
+		let values: Vec<_> = types
+			.iter()
+			.enumerate()
+			.map(|(col, t)| literal(&t.value(row.get(col).copied().unwrap_or_default())))
+			.collect();
+		writeln!(out, "{insert} ({});", values.join(", ")).unwrap();
+	}
+	out.push_str("COMMIT;\n");
+	out
+}
+
+/// Whether the database has a table called `name`
+fn has_table(conn: &Connection, name: &str) -> io::Result<bool> {
+	conn.query_row(
+		"SELECT 1 FROM sqlite_schema WHERE type = 'table' AND name = ?",
+		[name],
+		|_| Ok(()),
+	)
+	.optional()
+	.map(|found| found.is_some())
+	.map_err(to_io)
+}
+
+/// Create a table in the database at `path` holding the excerpt, returning the number of rows
+/// inserted.
+///
+/// Fails if the table already exists, rather than replacing what it holds.
+pub fn write_database(path: &Path, excerpt: &Excerpt) -> io::Result<usize> {
+	let types = column_types(excerpt);
+	let mut conn = Connection::open(path).map_err(to_io)?;
+	let tx = conn.transaction().map_err(to_io)?;
+	if has_table(&tx, excerpt.name)? {
+		return Err(io::Error::new(
+			ErrorKind::AlreadyExists,
+			format!("Table {:?} already exists in {path:?}", excerpt.name),
+		));
+	}
+	tx.execute(&create_table(excerpt, &types), [])
+		.map_err(to_io)?;
+	{
+		let params = vec!["?"; types.len()].join(", ");
+		let mut insert = tx
+			.prepare(&format!("{} ({params})", insert_into(excerpt)))
+			.map_err(to_io)?;
+		for row in &excerpt.rows {
+			let values = types
+				.iter()
+				.enumerate()
+				.map(|(col, t)| t.value(row.get(col).copied().unwrap_or_default()));
+			insert
+				.execute(rusqlite::params_from_iter(values))
+				.map_err(to_io)?;
+		}
+	}
+	tx.commit().map_err(to_io)?;
+	Ok(excerpt.rows.len())
+}
+
+/// A table in a SQLite database, edited as a grid with a header row of column names.
+///
+/// Rows are identified by their `rowid`, so edits are written back in place. Inserted rows are
+/// appended to the table, and appear in `rowid` order the next time it is read.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Table {
+	path: PathBuf,
+	name: String,
+	columns: Vec<Column>,
+	/// `rowid` of each row after the header, as of the last read or write
+	rowids: Vec<i64>,
+	/// Cells that were `NULL` as of the last read or write, by `rowid` and column
+	nulls: BTreeSet<(i64, usize)>,
+}
+
+/// A column of a table, with how to store cells edited in it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Column {
+	name: String,
+	/// Type to store cells as, or `None` to store each as the type it looks like
+	kind: Option<SqlType>,
+	nullable: bool,
+}
+
+impl Table {
+	/// Open `name` in the database at `path`, or its only table if no name is given.
+	pub fn open(path: &Path, name: Option<&str>) -> io::Result<Self> {
+		if !path.exists() {
+			return Err(io::Error::new(
+				ErrorKind::NotFound,
+				format!("No database at {path:?}"),
+			));
+		}
+		let conn = Connection::open(path).map_err(to_io)?;
+		let name = match name {
+			Some(name) => name.to_string(),
+			None => {
+				let mut stmt = conn
+					.prepare(
+						"SELECT name FROM sqlite_schema WHERE type = 'table' \
+						AND name NOT LIKE 'sqlite_%' ORDER BY name",
+					)
+					.map_err(to_io)?;
+				let mut tables = stmt
+					.query_map([], |r| r.get::<_, String>(0))
+					.and_then(|rows| rows.collect::<Result<Vec<_>, _>>())
+					.map_err(to_io)?;
+				if tables.len() != 1 {
+					return Err(io::Error::new(
+						ErrorKind::InvalidInput,
+						format!(
+							"Choose a table with {}:TABLE, from: {}",
+							path.display(),
+							tables.join(", ")
+						),
+					));
+				}
+				tables.remove(0)
+			}
+		};
+		if !has_table(&conn, &name)? {
+			return Err(io::Error::new(
+				ErrorKind::NotFound,
+				format!("No table {name:?} in {path:?}"),
+			));
+		}
+		Ok(Self {
+			path: path.to_path_buf(),
+			name,
+			columns: Vec::new(),
+			rowids: Vec::new(),
+			nulls: BTreeSet::new(),
+		})
+	}
+
+	/// Declared type and nullability of each column, by name
+	fn declared_columns(conn: &Connection, name: &str) -> io::Result<Vec<(String, String, bool)>> {
+		let mut stmt = conn
+			.prepare(&format!("PRAGMA table_xinfo({})", quote_ident(name)))
+			.map_err(to_io)?;
+		let rows = stmt
+			.query_map([], |r| {
+				let not_null: bool = r.get("notnull")?;
+				Ok((r.get("name")?, r.get("type")?, !not_null))
+			})
+			.and_then(|rows| rows.collect())
+			.map_err(to_io);
+		rows
+	}
+
+	/// Value to store for `cell` in the column `col`, of the row `id` if it was read.
+	///
+	/// Empty cells are `NULL` if they were when read, or are in new rows, and the column allows
+	/// it.
+	fn value(&self, cell: &str, col: usize, id: Option<i64>) -> Value {
+		let column = &self.columns[col];
+		if cell.is_empty() {
+			let was_null = id.is_none_or(|id| self.nulls.contains(&(id, col)));
+			return if column.nullable && was_null {
+				Value::Null
+			} else {
+				Value::Text(String::new())
+			};
+		}
+		column
+			.kind
+			.unwrap_or_else(|| SqlType::infer([cell]))
+			.value(cell)
+	}
+
+	pub fn read(&mut self) -> io::Result<Grid> {
+		let conn = Connection::open(&self.path).map_err(to_io)?;
+		let mut stmt = conn
+			.prepare(&format!(
+				"SELECT rowid, * FROM {} ORDER BY rowid",
+				quote_ident(&self.name)
+			))
+			.map_err(to_io)?;
+		let declared = Self::declared_columns(&conn, &self.name)?;
+		let columns: Vec<_> = stmt
+			.column_names()
+			.into_iter()
+			.skip(1)
+			.map(|name| {
+				let found = declared.iter().find(|(n, ..)| n == name);
+				Column {
+					name: name.to_string(),
+					kind: found.map_or(Some(SqlType::Text), |(_, t, _)| SqlType::affinity(t)),
+					nullable: found.is_none_or(|&(.., nullable)| nullable),
+				}
+			})
+			.collect();
+
+		let mut rowids = Vec::new();
+		let mut nulls = BTreeSet::new();
+		let mut cells = vec![columns.iter().map(|c| c.name.clone()).collect()];
+		let mut rows = stmt.query([]).map_err(to_io)?;
+		while let Some(row) = rows.next().map_err(to_io)? {
+			let id = row.get(0).map_err(to_io)?;
+			rowids.push(id);
+			let row = (1..=columns.len())
+				.map(|i| {
+					let value = row.get_ref(i)?;
+					if value == ValueRef::Null {
+						nulls.insert((id, i - 1));
+					}
+					Ok(cell(value))
+				})
+				.collect::<Result<_, _>>()
+				.map_err(to_io)?;
+			cells.push(row);
+		}
+
+		self.columns = columns;
+		self.rowids = rowids;
+		self.nulls = nulls;
+		Ok(Grid::from_rows(cells))
+	}
+
+	/// Apply the changes that turned the last read or written grid into `grid` to the table,
+	/// in a single transaction.
+	pub fn write(&mut self, changes: &[Change], grid: &Grid) -> io::Result<()> {
+		let unsupported = |what: &str| {
+			io::Error::new(
+				ErrorKind::Unsupported,
+				format!("Can't save {what} to a SQLite table"),
+			)
+		};
+
+		// replay the changes to follow rows as they move around
+		let mut rowids: Vec<Option<i64>> = self.rowids.iter().copied().map(Some).collect();
+		let mut edited = BTreeSet::new();
+		let mut deleted = Vec::new();
+		let mut pending: Vec<_> = changes.iter().rev().collect();
+		while let Some(change) = pending.pop() {
+			match change {
+				Change::Batch(batch) => pending.extend(batch.iter().rev()),
+				Change::Replace { pos, .. } if pos.y == 0 => {
+					return Err(unsupported("renamed columns"))
+				}
+				Change::Replace { pos, .. } => {
+					if let Some(id) = rowids[pos.y - 1] {
+						edited.insert((id, pos.x));
+					}
+				}
+				// inverse of an insertion, so the row is re-inserted
+				Change::DeleteRow { row, .. } if *row > 0 => rowids.insert(row - 1, None),
+				// inverse of a deletion, so the row is removed
+				Change::InsertRow { row } if *row > 0 => {
+					if let Some(id) = rowids.remove(row - 1) {
+						edited.retain(|&(edited, _)| edited != id);
+						deleted.push(id);
+					}
+				}
+				Change::DeleteRow { .. } => return Err(unsupported("rows above the column names")),
+				Change::InsertRow { .. } => return Err(unsupported("deleted column names")),
+				Change::DeleteCol { .. } => return Err(unsupported("added columns")),
+				Change::InsertCol { .. } => return Err(unsupported("deleted columns")),
+				Change::ReplaceGrid { .. } => {
+					return Err(unsupported("a sheet replaced as a whole"))
+				}
+			}
+		}
+		if rowids.len() + 1 != grid.size().y {
+			return Err(io::Error::other(format!(
+				"Lost track of the rows of the table, expected {} but there are {}",
+				rowids.len(),
+				grid.size().y.saturating_sub(1)
+			)));
+		}
+		let inserted = rowids.iter().filter(|r| r.is_none()).count();
+
+		// cells written, and whether they're now NULL
+		let mut written = Vec::new();
+		let table = quote_ident(&self.name);
+		let mut conn = Connection::open(&self.path).map_err(to_io)?;
+		let tx = conn.transaction().map_err(to_io)?;
+		{
+			let mut delete = tx
+				.prepare(&format!("DELETE FROM {table} WHERE rowid = ?"))
+				.map_err(to_io)?;
+			for id in &deleted {
+				delete.execute([id]).map_err(to_io)?;
+			}
+
+			for &(id, col) in &edited {
+				let y = 1 + rowids.iter().position(|&r| r == Some(id)).unwrap();
+				let value = self.value(grid.get(XY { x: col, y }).unwrap(), col, Some(id));
+				written.push(((id, col), value == Value::Null));
+				tx.execute(
+					&format!(
+						"UPDATE {table} SET {} = ? WHERE rowid = ?",
+						quote_ident(&self.columns[col].name)
+					),
+					rusqlite::params![value, id],
+				)
+				.map_err(to_io)?;
+			}
+
+			let columns: Vec<_> = self.columns.iter().map(|c| quote_ident(&c.name)).collect();
+			let params = vec!["?"; columns.len()].join(", ");
+			let mut insert = tx
+				.prepare(&format!(
+					"INSERT INTO {table} ({}) VALUES ({params})",
+					columns.join(", ")
+				))
+				.map_err(to_io)?;
+			for (i, id) in rowids.iter_mut().enumerate() {
+				if id.is_none() {
+					let values: Vec<_> = (0..self.columns.len())
+						.map(|x| self.value(grid.get(XY { x, y: i + 1 }).unwrap(), x, None))
+						.collect();
+					let nulls: Vec<_> = values.iter().map(|v| *v == Value::Null).collect();
+					let new = insert
+						.insert(rusqlite::params_from_iter(values))
+						.map_err(to_io)?;
+					written.extend(
+						nulls
+							.into_iter()
+							.enumerate()
+							.map(|(x, null)| ((new, x), null)),
+					);
+					*id = Some(new);
+				}
+			}
+		}
+		tx.commit().map_err(to_io)?;
+		info!(
+			"Updated {} cells, deleted {} rows, and inserted {} rows in {:?}",
+			edited.len(),
+			deleted.len(),
+			inserted,
+			self.name
+		);
+
+		self.rowids = rowids.into_iter().flatten().collect();
+		self.nulls.retain(|(id, _)| !deleted.contains(id));
+		for (cell, null) in written {
+			if null {
+				self.nulls.insert(cell);
+			} else {
+				self.nulls.remove(&cell);
+			}
+		}
+		Ok(())
+	}
+}
+
+fn cell(value: ValueRef) -> String {
+	match value {
+		ValueRef::Null => String::new(),
+		ValueRef::Integer(i) => i.to_string(),
+		ValueRef::Real(f) => f.to_string(),
+		ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned(),
+		ValueRef::Blob(b) => String::from_utf8_lossy(b).into_owned(),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn example() -> Excerpt<'static> {
+		Excerpt {
+			name: "people",
+			header: vec!["id", "name", "score"],
+			rows: vec![vec!["1", "O'Brien", "1.5"], vec!["2", "", "3"]],
+		}
+	}
+
+	#[test]
+	fn generates_statements() {
+		assert_eq!(
+			"\
+BEGIN TRANSACTION;
+CREATE TABLE \"people\" (\"id\" INTEGER, \"name\" TEXT, \"score\" REAL);
+INSERT INTO \"people\" (\"id\", \"name\", \"score\") VALUES (1, 'O''Brien', 1.5);
+INSERT INTO \"people\" (\"id\", \"name\", \"score\") VALUES (2, NULL, 3);
+COMMIT;
+",
+			statements(&example())
+		);
+	}
+
+	#[test]
+	fn parses_database_paths() {
+		assert_eq!(
+			Some((PathBuf::from("a/data.db"), Some("t"))),
+			database_path("a/data.db:t")
+		);
+		assert_eq!(
+			Some((PathBuf::from("data.sqlite"), None)),
+			database_path("data.sqlite")
+		);
+		assert_eq!(None, database_path("data.sql"));
+	}
+
+	fn temp_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("sht_{name}_{}", std::process::id()));
+		std::fs::create_dir_all(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn writes_database() {
+		let dir = temp_dir("writes_database");
+		let path = dir.join("test.db");
+		assert_eq!(2, write_database(&path, &example()).unwrap());
+		// leaves an existing table as it was
+		let mut other = example();
+		other.rows.truncate(1);
+		let e = write_database(&path, &other).unwrap_err();
+		assert_eq!(ErrorKind::AlreadyExists, e.kind());
+
+		let conn = Connection::open(&path).unwrap();
+		let total: f64 = conn
+			.query_row("SELECT sum(score) FROM people", [], |r| r.get(0))
+			.unwrap();
+		assert_eq!(4.5, total);
+		let nulls: i64 = conn
+			.query_row("SELECT count(*) FROM people WHERE name IS NULL", [], |r| {
+				r.get(0)
+			})
+			.unwrap();
+		assert_eq!(1, nulls);
+		drop(conn);
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn writes_table_changes() {
+		use crate::{grid::ChangeTracker, XY};
+
+		let dir = temp_dir("writes_table_changes");
+		let path = dir.join("test.db");
+		write_database(&path, &example()).unwrap();
+
+		let mut table = Table::open(&path, None).unwrap();
+		let mut grid = table.read().unwrap();
+		assert_eq!(
+			vec![
+				vec!["id", "name", "score"],
+				vec!["1", "O'Brien", "1.5"],
+				vec!["2", "", "3"]
+			],
+			grid.to_vec().unwrap()
+		);
+
+		let mut tracker = ChangeTracker::default();
+		grid.insert_row(1, vec!["3".into(), "new".into()])
+			.track(&mut tracker);
+		grid.edit(XY { x: 1, y: 3 }, "edited".into())
+			.track(&mut tracker);
+		grid.delete_row(2).track(&mut tracker);
+		table.write(&tracker.pending(&grid), &grid).unwrap();
+
+		let mut grid = table.read().unwrap();
+		assert_eq!(
+			vec![
+				vec!["id", "name", "score"],
+				vec!["2", "edited", "3"],
+				vec!["3", "new", ""]
+			],
+			grid.to_vec().unwrap()
+		);
+
+		let unsupported = |change: Change| {
+			let e = table.clone().write(&[change], &grid).unwrap_err();
+			e.to_string()
+		};
+		let row = vec![String::new(); 3];
+		assert!(unsupported(Change::Replace {
+			pos: XY { x: 0, y: 0 },
+			old: "id".into()
+		})
+		.contains("renamed columns"));
+		assert!(unsupported(Change::DeleteRow { row: 0, old: row }).contains("above the column"));
+		assert!(unsupported(Change::InsertRow { row: 0 }).contains("deleted column names"));
+		assert!(unsupported(Change::InsertCol { col: 1 }).contains("deleted columns"));
+		assert!(unsupported(Change::ReplaceGrid { old: grid.clone() }).contains("as a whole"));
+
+		// rows that weren't tracked can't be matched to the table
+		let _ = grid.insert_row(1, Vec::new());
+		assert!(table.write(&[], &grid).is_err());
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+
+	#[test]
+	fn writes_values_as_column_types() {
+		use crate::{grid::ChangeTracker, XY};
+
+		let dir = temp_dir("writes_values_as_column_types");
+		let path = dir.join("test.db");
+		let conn = Connection::open(&path).unwrap();
+		conn.execute_batch(
+			"CREATE TABLE t (n INTEGER NOT NULL, x REAL, s TEXT, note TEXT) STRICT;\
+			INSERT INTO t VALUES (1, 1.5, '', NULL);\
+			CREATE TABLE u (v NUMERIC);\
+			INSERT INTO u VALUES (1), (2);",
+		)
+		.unwrap();
+		let typeof_rows = |query: &str| -> Vec<String> {
+			conn.prepare(query)
+				.unwrap()
+				.query_map([], |r| r.get(0))
+				.unwrap()
+				.collect::<Result<_, _>>()
+				.unwrap()
+		};
+
+		let mut table = Table::open(&path, Some("t")).unwrap();
+		let mut grid = table.read().unwrap();
+		let mut tracker = ChangeTracker::default();
+		for (x, text) in [(0, "5"), (1, "2"), (2, "a"), (2, ""), (3, "b"), (3, "")] {
+			grid.edit(XY { x, y: 1 }, text.into()).track(&mut tracker);
+		}
+		grid.insert_row(2, vec!["6".into()]).track(&mut tracker);
+		table.write(&tracker.pending(&grid), &grid).unwrap();
+		assert_eq!(
+			vec!["integer,real,text,null", "integer,null,null,null"],
+			typeof_rows(
+				"SELECT typeof(n) || ',' || typeof(x) || ',' || typeof(s) || ',' || typeof(note) \
+				FROM t ORDER BY rowid"
+			)
+		);
+
+		// numeric columns store each value as the type it looks like
+		let mut table = Table::open(&path, Some("u")).unwrap();
+		let mut grid = table.read().unwrap();
+		let mut tracker = ChangeTracker::default();
+		grid.edit(XY { x: 0, y: 1 }, "x".into()).track(&mut tracker);
+		grid.edit(XY { x: 0, y: 2 }, "2.5".into())
+			.track(&mut tracker);
+		table.write(&tracker.pending(&grid), &grid).unwrap();
+		assert_eq!(
+			vec!["text", "real"],
+			typeof_rows("SELECT typeof(v) FROM u ORDER BY rowid")
+		);
+		drop(conn);
+		std::fs::remove_dir_all(dir).unwrap();
+	}
+}
@@ -1,14 +1,65 @@
-use std::{io, iter, mem};
+use std::{
+	borrow::Cow,
+	cmp::{min, Ordering},
+	collections::HashMap,
+	io, iter, mem,
+	ops::Range,
+	path::Path,
+	sync::Arc,
+};
 
 use crate::XY;
 
 use serde::{Deserialize, Serialize};
 
+mod source;
+pub use source::{CsvSource, Progress};
+
+/// Most rows read from a source file to keep in memory for display
+const CACHE_ROWS: usize = 4096;
+/// Number of rows read from a source file at a time when writing
+const BATCH_ROWS: usize = 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Piece {
+	/// Rows `start..start + len` of the source file
+	Source { start: usize, len: usize },
+	/// Rows held in memory
+	Local(Vec<Vec<String>>),
+}
+
+impl Piece {
+	fn len(&self) -> usize {
+		match self {
+			Piece::Source { len, .. } => *len,
+			Piece::Local(rows) => rows.len(),
+		}
+	}
+}
+
+/// Cells of a sheet, held in memory or read from a file as they're needed.
+///
+/// Files too large to load are indexed in the background and shown as they're indexed, with
+/// edited rows kept in memory on top of them until they're written.
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Grid {
-	cells: Vec<Vec<String>>,
+	/// Rows in order, as ranges of rows of the source or rows held in memory
+	pieces: Vec<Piece>,
+	/// Column of the source shown in each column, or `None` if it was added since
+	columns: Vec<Option<usize>>,
+	#[serde(skip)]
+	source: Option<Arc<CsvSource>>,
+	/// Rows of the source that have been read for display
+	#[serde(skip)]
+	cache: HashMap<usize, Vec<String>>,
+	/// Number of rows and columns of the source that have been added to the grid
+	indexed: XY<usize>,
 	/// Dimensions of cells
 	size: XY<usize>,
+	/// Error reading a row of the source to change it, which left the row empty, so writing the
+	/// grid would lose its contents
+	#[serde(skip)]
+	damaged: Option<String>,
 }
 
 impl Grid {
@@ -20,37 +71,302 @@ impl Grid {
 			.map(|r| r.iter().map(|s| s.to_string()).collect())
 			.collect();
 
+		Ok(Self::from_rows(cells))
+	}
+
+	/// Create a grid from rows of cells, padding shorter rows to the same width.
+	pub fn from_rows(mut cells: Vec<Vec<String>>) -> Self {
 		let height = cells.len();
-		let width = if height == 0 { 0 } else { cells[0].len() };
+		let width = cells.iter().map(|r| r.len()).max().unwrap_or_default();
+		for row in &mut cells {
+			row.resize(width, String::new());
+		}
 		let size = XY {
 			x: width,
 			y: height,
 		};
 
-		Ok(Self { cells, size })
+		Self {
+			pieces: vec![Piece::Local(cells)],
+			columns: vec![None; width],
+			size,
+			..Default::default()
+		}
 	}
 
-	pub fn to_csv<W: io::Write>(&self, wtr: &mut csv::Writer<W>) -> io::Result<()> {
-		for row in &self.cells {
-			wtr.write_record(row)?;
+	/// Create a grid backed by a CSV file, which is indexed in the background.
+	///
+	/// The grid starts out empty and grows with each call to `refresh`.
+	pub fn open_csv(path: &Path) -> io::Result<Self> {
+		Ok(Self {
+			source: Some(Arc::new(CsvSource::open(path)?)),
+			..Default::default()
+		})
+	}
+
+	/// Whether every row is held in memory, instead of some being read from a file as needed
+	pub fn is_loaded(&self) -> bool {
+		self.pieces.iter().all(|p| matches!(p, Piece::Local(_)))
+	}
+
+	/// Indexing progress of the file backing the grid, if any
+	pub fn progress(&self) -> Option<Progress> {
+		self.source.as_ref().map(|s| s.progress())
+	}
+
+	/// Add rows to the end without tracking them as a change, for rows appended to the file.
+	pub fn append_rows(&mut self, rows: Vec<Vec<String>>) {
+		let width = rows.iter().map(Vec::len).max().unwrap_or_default();
+		while self.size.x < width {
+			let _ = self.insert_col(self.size.x, Vec::new());
 		}
-		Ok(())
+		for row in rows {
+			let _ = self.insert_row(self.size.y, row);
+		}
+	}
+
+	/// Stop indexing the file backing the grid, if any.
+	pub fn cancel(&self) {
+		if let Some(source) = &self.source {
+			source.cancel();
+		}
+	}
+
+	/// Add any rows and columns of the source that have been indexed since the last call,
+	/// returning whether the grid grew.
+	pub fn refresh(&mut self) -> bool {
+		let Some(progress) = self.progress() else {
+			return false;
+		};
+		let XY { x: width, y: rows } = self.indexed;
+		if progress.width > width {
+			let added = progress.width - width;
+			self.columns.extend((width..progress.width).map(Some));
+			for piece in &mut self.pieces {
+				if let Piece::Local(rows) = piece {
+					for row in rows {
+						row.extend(iter::repeat_n(String::new(), added));
+					}
+				}
+			}
+			self.size.x += added;
+		}
+		if progress.rows > rows {
+			let added = progress.rows - rows;
+			match self.pieces.last_mut() {
+				Some(Piece::Source { start, len }) if *start + *len == rows => *len += added,
+				_ => self.pieces.push(Piece::Source {
+					start: rows,
+					len: added,
+				}),
+			}
+			self.size.y += added;
+		}
+		let grew = self.indexed
+			!= XY {
+				x: progress.width,
+				y: progress.rows,
+			};
+		self.indexed = XY {
+			x: self.indexed.x.max(progress.width),
+			y: self.indexed.y.max(progress.rows),
+		};
+		grew
 	}
 
-	pub fn cells(&self) -> &Vec<Vec<String>> {
-		&self.cells
+	/// Read the rows of the source within `rows` that aren't in memory, so they can be displayed.
+	pub fn load(&mut self, rows: Range<usize>) {
+		let Some(source) = self.source.clone() else {
+			return;
+		};
+		let rows = rows.start.min(self.size.y)..rows.end.min(self.size.y);
+		let mut wanted = Vec::new();
+		let mut y = 0;
+		for piece in &self.pieces {
+			let piece_rows = y..y + piece.len();
+			y = piece_rows.end;
+			let &Piece::Source { start, .. } = piece else {
+				continue;
+			};
+			let overlap = rows.start.max(piece_rows.start)..rows.end.min(piece_rows.end);
+			if !overlap.is_empty() {
+				let skip = overlap.start - piece_rows.start;
+				wanted.push(start + skip..start + skip + overlap.len());
+			}
+		}
+
+		if self.cache.len() > CACHE_ROWS {
+			self.cache
+				.retain(|row, _| wanted.iter().any(|r| r.contains(row)));
+		}
+		for range in wanted {
+			let Some(missing) = range.clone().find(|r| !self.cache.contains_key(r)) else {
+				continue;
+			};
+			match source.read(missing, range.end - missing) {
+				Ok(rows) => self.cache.extend((missing..).zip(rows)),
+				Err(e) => error!("Error reading rows {range:?}: {e}"),
+			}
+		}
+	}
+
+	/// Every row in order, reading any that aren't in memory from the source, or an error first
+	/// if a row couldn't be read to change it.
+	pub fn rows(&self) -> impl Iterator<Item = io::Result<Cow<'_, [String]>>> + '_ {
+		let damaged = self
+			.damaged
+			.iter()
+			.map(|e| Err(io::Error::other(e.clone())));
+		damaged.chain(self.pieces.iter().flat_map(move |piece| {
+			match piece {
+				Piece::Local(rows) => {
+					Box::new(rows.iter().map(|r| Ok(Cow::Borrowed(r.as_slice()))))
+						as Box<dyn Iterator<Item = _>>
+				}
+				&Piece::Source { start, len } => Box::new(
+					(start..start + len)
+						.step_by(BATCH_ROWS)
+						.flat_map(move |batch| {
+							let count = min(BATCH_ROWS, start + len - batch);
+							match self.read_source(batch, count) {
+								Ok(rows) => rows.into_iter().map(|r| Ok(Cow::Owned(r))).collect(),
+								Err(e) => vec![Err(e)],
+							}
+						}),
+				),
+			}
+		}))
+	}
+
+	/// Every row, reading any that aren't in memory from the source.
+	pub fn to_vec(&self) -> io::Result<Vec<Vec<String>>> {
+		self.rows().map(|r| r.map(Cow::into_owned)).collect()
+	}
+
+	/// Rows of the source, with columns in the order of the grid.
+	fn read_source(&self, start: usize, count: usize) -> io::Result<Vec<Vec<String>>> {
+		let source = self
+			.source
+			.as_ref()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Source file isn't open"))?;
+		let rows = source.read(start, count)?;
+		if rows.len() < count {
+			return Err(io::Error::new(
+				io::ErrorKind::UnexpectedEof,
+				format!("Expected {count} rows from row {start} of the source file"),
+			));
+		}
+		Ok(rows.iter().map(|row| self.arrange(row)).collect())
+	}
+
+	/// Put the fields of a source row in the order of the grid's columns.
+	fn arrange(&self, row: &[String]) -> Vec<String> {
+		self.columns
+			.iter()
+			.map(|c| c.and_then(|c| row.get(c).cloned()).unwrap_or_default())
+			.collect()
+	}
+
+	pub fn to_csv<W: io::Write>(&self, wtr: &mut csv::Writer<W>) -> io::Result<()> {
+		for row in self.rows() {
+			wtr.write_record(row?.iter())?;
+		}
+		Ok(())
 	}
 
 	pub fn size(&self) -> XY<usize> {
 		self.size
 	}
 
+	/// Index of the piece holding a row, and the row's offset within it
+	fn locate(&self, row: usize) -> Option<(usize, usize)> {
+		let mut y = 0;
+		for (i, piece) in self.pieces.iter().enumerate() {
+			if row < y + piece.len() {
+				return Some((i, row - y));
+			}
+			y += piece.len();
+		}
+		None
+	}
+
+	/// Get the contents of a cell, if it is in memory.
 	pub fn get(&self, pos: XY<usize>) -> Option<&String> {
-		self.cells.get(pos.y).and_then(|r| r.get(pos.x))
+		static EMPTY: String = String::new();
+		let (i, offset) = self.locate(pos.y)?;
+		match &self.pieces[i] {
+			Piece::Local(rows) => rows[offset].get(pos.x),
+			&Piece::Source { start, .. } => {
+				let row = self.cache.get(&(start + offset))?;
+				match self.columns.get(pos.x)? {
+					Some(c) => Some(row.get(*c).unwrap_or(&EMPTY)),
+					None => Some(&EMPTY),
+				}
+			}
+		}
 	}
 
-	fn get_mut(&mut self, pos: XY<usize>) -> Option<&mut String> {
-		self.cells.get_mut(pos.y).and_then(|r| r.get_mut(pos.x))
+	/// Move a row from the source into memory so it can be changed, returning its piece.
+	fn materialize(&mut self, row: usize) -> (usize, usize) {
+		let (i, offset) = self.locate(row).expect("row is in grid");
+		let Piece::Source { start, len } = self.pieces[i] else {
+			return (i, offset);
+		};
+		let source_row = start + offset;
+		let cells = match self.cache.get(&source_row) {
+			Some(cells) => Ok(self.arrange(cells)),
+			None => self
+				.read_source(source_row, 1)
+				.map(|mut rows| rows.remove(0)),
+		}
+		.unwrap_or_else(|e| {
+			error!("Error reading row {source_row} of the source file: {e}");
+			self.damaged.get_or_insert_with(|| {
+				format!(
+					"row {} of the file couldn't be read, so its contents would be lost: {e}",
+					row + 1
+				)
+			});
+			vec![String::new(); self.size.x]
+		});
+
+		let before = Piece::Source { start, len: offset };
+		let after = Piece::Source {
+			start: source_row + 1,
+			len: len - offset - 1,
+		};
+		let pieces = [before, Piece::Local(vec![cells]), after];
+		let pieces: Vec<_> = pieces.into_iter().filter(|p| p.len() > 0).collect();
+		let local = if offset > 0 { i + 1 } else { i };
+		self.pieces.splice(i..=i, pieces);
+		(local, 0)
+	}
+
+	/// Call `f` with the column and contents of each cell that is in memory.
+	pub fn visit_loaded(&self, mut f: impl FnMut(usize, &str)) {
+		for piece in &self.pieces {
+			if let Piece::Local(rows) = piece {
+				for row in rows {
+					for (x, cell) in row.iter().enumerate() {
+						f(x, cell);
+					}
+				}
+			}
+		}
+		for row in self.cache.values() {
+			for (x, c) in self.columns.iter().enumerate() {
+				if let Some(cell) = c.and_then(|c| row.get(c)) {
+					f(x, cell);
+				}
+			}
+		}
+	}
+
+	fn local_rows_mut(&mut self) -> impl Iterator<Item = &mut Vec<String>> {
+		self.pieces.iter_mut().flat_map(|p| match p {
+			Piece::Local(rows) => rows.iter_mut(),
+			Piece::Source { .. } => [].iter_mut(),
+		})
 	}
 
 	pub fn is_in(&self, pos: XY<usize>) -> bool {
@@ -58,31 +374,121 @@ impl Grid {
 	}
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChangeTracker {
 	undos: Vec<Change>,
 	redos: Vec<Change>,
+	/// Number of undos when the grid last matched the file, if still reachable
+	saved: Option<usize>,
+	/// Incremented whenever the tracked grid changes
+	version: u64,
+}
+
+impl Default for ChangeTracker {
+	fn default() -> Self {
+		Self {
+			undos: Default::default(),
+			redos: Default::default(),
+			saved: Some(0),
+			version: 0,
+		}
+	}
 }
 
 impl ChangeTracker {
 	/// Record a new change, dropping any possible redos
 	pub fn push(&mut self, change: Change) {
 		drop(self.redos.drain(..));
+		if self.saved.is_some_and(|s| s > self.undos.len()) {
+			// saved state was in the dropped redos
+			self.saved = None;
+		}
 		self.undos.push(change);
+		self.version += 1;
 	}
 
-	pub fn undo(&mut self, g: &mut Grid) -> Option<()> {
+	/// Undo the last change, returning the change that redoes it
+	pub fn undo(&mut self, g: &mut Grid) -> Option<&Change> {
 		let change = self.undos.pop()?;
-		let redo = g.undo(change);
+		let redo = g.apply(change);
 		self.redos.push(redo);
-		Some(())
+		self.version += 1;
+		self.redos.last()
 	}
 
-	pub fn redo(&mut self, g: &mut Grid) -> Option<()> {
+	/// Redo the last undone change, returning the change that undoes it
+	pub fn redo(&mut self, g: &mut Grid) -> Option<&Change> {
 		let change = self.redos.pop()?;
-		let undo = g.undo(change);
+		let undo = g.apply(change);
 		self.undos.push(undo);
-		Some(())
+		self.version += 1;
+		self.undos.last()
+	}
+
+	/// Number of changes that can be undone
+	pub fn undo_len(&self) -> usize {
+		self.undos.len()
+	}
+
+	/// Changes recorded since there were `len` to undo, oldest first
+	pub fn undos_since(&self, len: usize) -> &[Change] {
+		self.undos.get(len..).unwrap_or_default()
+	}
+
+	/// Combine the changes recorded since there were `len` to undo, so they're undone and redone
+	/// as one.
+	pub fn squash(&mut self, len: usize) {
+		if self.undos.len() <= len + 1 {
+			return;
+		}
+		if self.saved.is_some_and(|s| s > len && s < self.undos.len()) {
+			// saved state was partway through the combined changes
+			self.saved = None;
+		} else if self.saved == Some(self.undos.len()) {
+			self.saved = Some(len + 1);
+		}
+		let mut undos = self.undos.split_off(len);
+		undos.reverse();
+		self.undos.push(Change::Batch(undos));
+	}
+
+	/// Mark the current state as matching the file on disk
+	pub fn mark_saved(&mut self) {
+		self.saved = Some(self.undos.len());
+	}
+
+	/// Forget which state matches the file on disk, after writing one that's since been changed
+	pub fn forget_saved(&mut self) {
+		self.saved = None;
+	}
+
+	/// Whether there are changes that haven't been written to disk
+	pub fn is_modified(&self) -> bool {
+		self.saved != Some(self.undos.len())
+	}
+
+	/// Counter that changes whenever the grid is modified, undone, or redone
+	pub fn version(&self) -> u64 {
+		self.version
+	}
+
+	/// Changes that bring the saved version of the grid up to date with `g`, in order.
+	pub fn pending(&self, g: &Grid) -> Vec<Change> {
+		let Some(saved) = self.saved.filter(|&s| s <= self.undos.len()) else {
+			return vec![Change::ReplaceGrid { old: g.clone() }];
+		};
+		let mut g = g.clone();
+		let mut batches: Vec<_> = self.undos[saved..]
+			.iter()
+			.rev()
+			.map(|c| g.apply(c.clone()))
+			.collect();
+		batches.reverse();
+		let mut changes = Vec::new();
+		for change in batches {
+			change.flatten_into(&mut changes);
+		}
+		changes
 	}
 }
 
@@ -91,31 +497,98 @@ impl ChangeTracker {
 #[must_use = "Changes must be recorded to correctly track history"]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Change {
-	Replace { pos: XY<usize>, old: String },
-	ReplaceGrid { old: Grid },
-	DeleteCol { col: usize, old: Vec<String> },
-	InsertCol { col: usize },
-	DeleteRow { row: usize, old: Vec<String> },
-	InsertRow { row: usize },
+	Replace {
+		pos: XY<usize>,
+		old: String,
+	},
+	ReplaceGrid {
+		old: Grid,
+	},
+	DeleteCol {
+		col: usize,
+		/// Contents of the rows in memory
+		old: Vec<String>,
+		/// Column of the source file shown in the column
+		#[serde(default)]
+		source: Option<usize>,
+	},
+	InsertCol {
+		col: usize,
+	},
+	DeleteRow {
+		row: usize,
+		old: Vec<String>,
+	},
+	InsertRow {
+		row: usize,
+	},
+	/// Changes made together, undone and redone as one
+	Batch(Vec<Change>),
 }
 
 impl Change {
 	pub fn track(self, tracker: &mut ChangeTracker) {
 		tracker.push(self);
 	}
+
+	/// Add the changes of any batches to `changes` in order.
+	fn flatten_into(self, changes: &mut Vec<Change>) {
+		match self {
+			Change::Batch(batch) => batch.into_iter().for_each(|c| c.flatten_into(changes)),
+			change => changes.push(change),
+		}
+	}
 }
 
 impl Grid {
-	fn undo(&mut self, change: Change) -> Change {
-		trace!("Undoing: {change:?}");
+	/// Apply a change, returning the change that reverses it
+	pub fn apply(&mut self, change: Change) -> Change {
+		trace!("Applying: {change:?}");
 		use Change::*;
 		match change {
 			Replace { pos, old } => self.edit(pos, old),
 			ReplaceGrid { old } => self.replace(old),
-			DeleteCol { col, old } => self.insert_col(col, old),
+			DeleteCol { col, old, source } => self.restore_col(col, old, source),
 			InsertCol { col } => self.delete_col(col),
 			DeleteRow { row, old } => self.insert_row(row, old),
 			InsertRow { row } => self.delete_row(row),
+			Batch(changes) => {
+				let mut undos: Vec<_> = changes.into_iter().map(|c| self.apply(c)).collect();
+				undos.reverse();
+				Batch(undos)
+			}
+		}
+	}
+
+	/// Apply a change like `apply`, or return an error if it doesn't fit the grid, like one made
+	/// to a different version of the file.
+	pub fn try_apply(&mut self, change: Change) -> io::Result<Change> {
+		use Change::*;
+		let size = self.size;
+		let fits = match &change {
+			Replace { pos, .. } => self.is_in(*pos),
+			ReplaceGrid { .. } | Batch(_) => true,
+			DeleteCol { col, old, .. } => *col <= size.x && old.len() <= size.y,
+			InsertCol { col } => *col < size.x,
+			DeleteRow { row, old } => *row <= size.y && old.len() <= size.x,
+			InsertRow { row } => *row < size.y,
+		};
+		if !fits {
+			return Err(io::Error::new(
+				io::ErrorKind::InvalidData,
+				format!("a change doesn't fit the {}x{} sheet", size.x, size.y),
+			));
+		}
+		match change {
+			Batch(changes) => {
+				let mut undos = changes
+					.into_iter()
+					.map(|c| self.try_apply(c))
+					.collect::<io::Result<Vec<_>>>()?;
+				undos.reverse();
+				Ok(Batch(undos))
+			}
+			change => Ok(self.apply(change)),
 		}
 	}
 
@@ -125,7 +598,12 @@ impl Grid {
 	}
 
 	pub fn edit(&mut self, pos: XY<usize>, contents: String) -> Change {
-		let old = mem::replace(self.get_mut(pos).unwrap(), contents);
+		assert!(pos.x < self.size.x);
+		let (i, offset) = self.materialize(pos.y);
+		let Piece::Local(rows) = &mut self.pieces[i] else {
+			unreachable!()
+		};
+		let old = mem::replace(&mut rows[offset][pos.x], contents);
 		Change::Replace { pos, old }
 	}
 
@@ -133,37 +611,269 @@ impl Grid {
 		assert!(row <= self.size.y);
 		assert!(contents.len() <= self.size.x);
 		if contents.len() < self.size.x {
-			contents.extend(iter::repeat(String::new()).take(self.size.x - contents.len()))
+			contents.extend(iter::repeat_n(String::new(), self.size.x - contents.len()))
+		}
+		match self.locate(row) {
+			None => match self.pieces.last_mut() {
+				Some(Piece::Local(rows)) => rows.push(contents),
+				_ => self.pieces.push(Piece::Local(vec![contents])),
+			},
+			Some((i, offset)) => match &mut self.pieces[i] {
+				Piece::Local(rows) => rows.insert(offset, contents),
+				&mut Piece::Source { start, len } => {
+					let pieces = [
+						Piece::Source { start, len: offset },
+						Piece::Local(vec![contents]),
+						Piece::Source {
+							start: start + offset,
+							len: len - offset,
+						},
+					];
+					self.pieces
+						.splice(i..=i, pieces.into_iter().filter(|p| p.len() > 0));
+				}
+			},
 		}
-		self.cells.insert(row, contents);
 		self.size.y += 1;
 		Change::InsertRow { row }
 	}
 
 	pub fn delete_row(&mut self, row: usize) -> Change {
 		assert!(row < self.size.y);
-		let old = self.cells.remove(row);
+		let (i, offset) = self.materialize(row);
+		let Piece::Local(rows) = &mut self.pieces[i] else {
+			unreachable!()
+		};
+		let old = rows.remove(offset);
+		if rows.is_empty() {
+			self.pieces.remove(i);
+		}
 		self.size.y -= 1;
 		Change::DeleteRow { row, old }
 	}
 
-	pub fn insert_col(&mut self, col: usize, mut contents: Vec<String>) -> Change {
+	/// Insert a column, with `contents` for the rows in memory, which is every row unless the
+	/// grid is backed by a file.
+	pub fn insert_col(&mut self, col: usize, contents: Vec<String>) -> Change {
+		self.restore_col(col, contents, None)
+	}
+
+	fn restore_col(&mut self, col: usize, contents: Vec<String>, source: Option<usize>) -> Change {
 		assert!(col <= self.size.x);
 		assert!(contents.len() <= self.size.y);
-		if contents.len() < self.size.y {
-			contents.extend(iter::repeat(String::new()).take(self.size.y - contents.len()))
-		}
-		for (row, text) in self.cells.iter_mut().zip(contents) {
-			row.insert(col, text);
+		let mut contents = contents.into_iter();
+		for row in self.local_rows_mut() {
+			row.insert(col, contents.next().unwrap_or_default());
 		}
+		self.columns.insert(col, source);
 		self.size.x += 1;
 		Change::InsertCol { col }
 	}
 
+	/// Sort `rows` by their cells in `col`, returning the cells that moved as one change.
+	pub fn sort_rows(&mut self, rows: Range<usize>, col: usize, descending: bool) -> Change {
+		assert!(col < self.size.x);
+		assert!(rows.end <= self.size.y);
+		self.load(rows.clone());
+		let mut sorted: Vec<Vec<String>> = rows
+			.clone()
+			.map(|y| {
+				(0..self.size.x)
+					.map(|x| self.get(XY { x, y }).cloned().unwrap_or_default())
+					.collect()
+			})
+			.collect();
+		sorted.sort_by(|a, b| {
+			let order = compare_cells(&a[col], &b[col]);
+			if descending {
+				order.reverse()
+			} else {
+				order
+			}
+		});
+		let mut changes = Vec::new();
+		for (y, row) in rows.zip(sorted) {
+			for (x, cell) in row.into_iter().enumerate() {
+				let pos = XY { x, y };
+				if self.get(pos) != Some(&cell) {
+					changes.push(self.edit(pos, cell));
+				}
+			}
+		}
+		Change::Batch(changes)
+	}
+
 	pub fn delete_col(&mut self, col: usize) -> Change {
 		assert!(col < self.size.x);
-		let old = self.cells.iter_mut().map(|row| row.remove(col)).collect();
+		let old = self.local_rows_mut().map(|row| row.remove(col)).collect();
+		let source = self.columns.remove(col);
 		self.size.x -= 1;
-		Change::DeleteCol { col, old }
+		Change::DeleteCol { col, old, source }
+	}
+}
+
+/// Order of cells when sorting: numbers by value, then everything else by text
+fn compare_cells(a: &str, b: &str) -> Ordering {
+	match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+		(Ok(a), Ok(b)) => a.total_cmp(&b),
+		(Ok(_), Err(_)) => Ordering::Less,
+		(Err(_), Ok(_)) => Ordering::Greater,
+		(Err(_), Err(_)) => a.cmp(b),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn edits_grid_backed_by_file() {
+		let path = std::env::temp_dir().join(format!("sht_lazy_{}.csv", std::process::id()));
+		let rows: Vec<Vec<String>> = (0..1000)
+			.map(|y| (0..3).map(|x| format!("{x}:{y}")).collect())
+			.collect();
+		let mut wtr = csv::Writer::from_path(&path).unwrap();
+		for row in &rows {
+			wtr.write_record(row).unwrap();
+		}
+		wtr.flush().unwrap();
+
+		let mut lazy = Grid::open_csv(&path).unwrap();
+		while !lazy.progress().unwrap().done {
+			std::thread::yield_now();
+		}
+		lazy.refresh();
+		let original = Grid::from_rows(rows.clone());
+		let mut eager = Grid::from_rows(rows);
+		assert_eq!(eager.size(), lazy.size());
+
+		let mut tracker = ChangeTracker::default();
+		for grid in [&mut eager, &mut lazy] {
+			tracker = ChangeTracker::default();
+			grid.edit(XY { x: 1, y: 500 }, "edited".into())
+				.track(&mut tracker);
+			grid.insert_row(300, vec!["new".into()]).track(&mut tracker);
+			grid.delete_row(700).track(&mut tracker);
+			grid.delete_col(0).track(&mut tracker);
+			grid.insert_col(2, Vec::new()).track(&mut tracker);
+		}
+		assert_eq!(eager.to_vec().unwrap(), lazy.to_vec().unwrap());
+		lazy.load(499..503);
+		assert_eq!(Some(&"edited".to_string()), lazy.get(XY { x: 0, y: 501 }));
+
+		while tracker.undo(&mut lazy).is_some() {}
+		assert_eq!(original.to_vec().unwrap(), lazy.to_vec().unwrap());
+		let _ = std::fs::remove_file(&path);
+	}
+
+	#[test]
+	fn refuses_to_write_rows_that_could_not_be_read() {
+		let path = std::env::temp_dir().join(format!("sht_damaged_{}.csv", std::process::id()));
+		let text: String = (0..100).map(|y| format!("{y},{y}\n")).collect();
+		std::fs::write(&path, text).unwrap();
+		let mut lazy = Grid::open_csv(&path).unwrap();
+		while !lazy.progress().unwrap().done {
+			std::thread::yield_now();
+		}
+		lazy.refresh();
+		assert!(lazy.to_vec().is_ok());
+
+		std::fs::write(&path, "0,0\n").unwrap();
+		let mut tracker = ChangeTracker::default();
+		lazy.edit(XY { x: 0, y: 50 }, "edited".into())
+			.track(&mut tracker);
+		tracker.undo(&mut lazy);
+		let e = lazy.to_vec().unwrap_err();
+		assert!(
+			e.to_string()
+				.starts_with("row 51 of the file couldn't be read"),
+			"{e}"
+		);
+		let _ = std::fs::remove_file(&path);
+	}
+
+	#[test]
+	fn reports_invalid_utf8_read_as_needed() {
+		let path = std::env::temp_dir().join(format!("sht_invalid_{}.csv", std::process::id()));
+		std::fs::write(&path, b"a,b\n\"c\nd\",\xFF\ne,\xFE\xFF\n").unwrap();
+		let mut lazy = Grid::open_csv(&path).unwrap();
+		while !lazy.progress().unwrap().done {
+			std::thread::yield_now();
+		}
+		lazy.refresh();
+		let progress = lazy.progress().unwrap();
+		assert_eq!(3, progress.invalid);
+		assert_eq!(Some(3), progress.first_invalid_line);
+		assert_eq!("\u{FFFD}", lazy.to_vec().unwrap()[1][1]);
+		let _ = std::fs::remove_file(&path);
+	}
+
+	#[test]
+	fn sorts_rows() {
+		let rows = |cells: &[[&str; 2]]| -> Vec<Vec<String>> {
+			cells
+				.iter()
+				.map(|row| row.iter().map(|c| c.to_string()).collect())
+				.collect()
+		};
+		let original = rows(&[
+			["name", "n"],
+			["b", "10"],
+			["a", "9"],
+			["c", "x"],
+			["d", "9"],
+		]);
+		let mut grid = Grid::from_rows(original.clone());
+		let mut tracker = ChangeTracker::default();
+		grid.sort_rows(1..5, 1, false).track(&mut tracker);
+		assert_eq!(
+			rows(&[
+				["name", "n"],
+				["a", "9"],
+				["d", "9"],
+				["b", "10"],
+				["c", "x"],
+			]),
+			grid.to_vec().unwrap()
+		);
+		grid.sort_rows(1..5, 0, true).track(&mut tracker);
+		assert_eq!(
+			rows(&[
+				["name", "n"],
+				["d", "9"],
+				["c", "x"],
+				["b", "10"],
+				["a", "9"],
+			]),
+			grid.to_vec().unwrap()
+		);
+		while tracker.undo(&mut grid).is_some() {}
+		assert_eq!(original, grid.to_vec().unwrap());
+	}
+
+	#[test]
+	fn squashes_changes() {
+		let rows: Vec<Vec<String>> = (0..3).map(|y| vec![y.to_string()]).collect();
+		let mut grid = Grid::from_rows(rows.clone());
+		let mut tracker = ChangeTracker::default();
+		grid.edit(XY { x: 0, y: 0 }, "edited".into())
+			.track(&mut tracker);
+		let len = tracker.undo_len();
+		for row in 1..4 {
+			grid.insert_row(row, vec![format!("new {row}")])
+				.track(&mut tracker);
+		}
+		tracker.squash(len);
+		assert_eq!(2, tracker.undo_len());
+		assert_eq!(6, grid.size().y);
+
+		tracker.undo(&mut grid).unwrap();
+		assert_eq!(3, grid.size().y);
+		assert_eq!(Some(&"edited".to_string()), grid.get(XY { x: 0, y: 0 }));
+		tracker.redo(&mut grid).unwrap();
+		assert_eq!(Some(&"new 3".to_string()), grid.get(XY { x: 0, y: 3 }));
+		while tracker.undo(&mut grid).is_some() {}
+		assert_eq!(rows, grid.to_vec().unwrap());
+		assert!(!tracker.is_modified());
 	}
 }
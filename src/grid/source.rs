@@ -0,0 +1,232 @@
+//! Rows of a CSV file too big to load at once, read from disk as they're needed.
+use std::{
+	fs::File,
+	io::{self, BufReader, Seek, SeekFrom},
+	path::Path,
+	sync::{
+		atomic::{AtomicBool, Ordering},
+		Arc, Mutex, MutexGuard,
+	},
+	thread,
+};
+
+use csv::{ByteRecord, ReaderBuilder};
+
+/// Number of records between each offset kept in the index
+const STRIDE: usize = 256;
+/// Number of records indexed between updates of the shared index
+const BATCH: usize = 1 << 14;
+
+/// Byte offsets of every `STRIDE`th record of the file
+#[derive(Debug, Default)]
+struct Index {
+	offsets: Vec<u64>,
+	rows: usize,
+	/// Most fields in any record
+	width: usize,
+	bytes: u64,
+	done: bool,
+	error: Option<String>,
+	invalid: usize,
+	first_invalid_line: Option<usize>,
+}
+
+/// How far the source has been indexed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+	pub rows: usize,
+	pub width: usize,
+	pub bytes: u64,
+	pub total: u64,
+	pub done: bool,
+	pub error: Option<String>,
+	/// Number of byte sequences that aren't valid UTF-8, shown replaced with `U+FFFD`
+	pub invalid: usize,
+	/// Line (starting at 1) of the first invalid sequence, if any
+	pub first_invalid_line: Option<usize>,
+}
+
+/// A CSV file indexed by a background thread
+#[derive(Debug)]
+pub struct CsvSource {
+	/// Kept open so rows can still be read after the file is replaced on save
+	file: Mutex<File>,
+	len: u64,
+	index: Arc<Mutex<Index>>,
+	cancel: Arc<AtomicBool>,
+}
+
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+	m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Number of sequences in a record that aren't valid UTF-8, and the line of the first.
+fn invalid_utf8(record: &ByteRecord) -> (usize, Option<usize>) {
+	if std::str::from_utf8(record.as_slice()).is_ok() {
+		return (0, None);
+	}
+	let mut line = record.position().map_or(1, |p| p.line() as usize);
+	let mut invalid = 0;
+	let mut first = None;
+	for chunk in record.iter().flat_map(<[u8]>::utf8_chunks) {
+		line += chunk.valid().matches('\n').count();
+		if !chunk.invalid().is_empty() {
+			invalid += 1;
+			first.get_or_insert(line);
+		}
+	}
+	(invalid, first)
+}
+
+fn fields(record: &ByteRecord) -> Vec<String> {
+	record
+		.iter()
+		.map(|f| String::from_utf8_lossy(f).into_owned())
+		.collect()
+}
+
+impl CsvSource {
+	/// Open the file and start indexing it in the background.
+	pub fn open(path: &Path) -> io::Result<Self> {
+		let file = File::open(path)?;
+		let len = file.metadata()?.len();
+		let index = Arc::<Mutex<Index>>::default();
+		let cancel = Arc::<AtomicBool>::default();
+		{
+			let file = File::open(path)?;
+			let index = index.clone();
+			let cancel = cancel.clone();
+			thread::Builder::new()
+				.name("csv index".to_string())
+				.spawn(move || build_index(file, &index, &cancel))?;
+		}
+		Ok(Self {
+			file: Mutex::new(file),
+			len,
+			index,
+			cancel,
+		})
+	}
+
+	pub fn progress(&self) -> Progress {
+		let index = lock(&self.index);
+		Progress {
+			rows: index.rows,
+			width: index.width,
+			bytes: index.bytes,
+			total: self.len,
+			done: index.done,
+			error: index.error.clone(),
+			invalid: index.invalid,
+			first_invalid_line: index.first_invalid_line,
+		}
+	}
+
+	/// Stop indexing, keeping the rows indexed so far.
+	pub fn cancel(&self) {
+		self.cancel.store(true, Ordering::Relaxed);
+	}
+
+	/// Read up to `count` records starting at `start`, if they've been indexed.
+	pub fn read(&self, start: usize, count: usize) -> io::Result<Vec<Vec<String>>> {
+		let (offset, end) = {
+			let index = lock(&self.index);
+			let Some(&offset) = index.offsets.get(start / STRIDE) else {
+				return Ok(Vec::new());
+			};
+			(offset, index.rows.min(start + count))
+		};
+
+		let mut file = lock(&self.file);
+		file.seek(SeekFrom::Start(offset))?;
+		let mut rdr = ReaderBuilder::new()
+			.has_headers(false)
+			.flexible(true)
+			.from_reader(BufReader::new(&mut *file));
+		let mut record = ByteRecord::new();
+		let mut rows = Vec::with_capacity(end.saturating_sub(start));
+		for i in (start / STRIDE * STRIDE)..end {
+			if !rdr.read_byte_record(&mut record)? {
+				break;
+			}
+			if i >= start {
+				rows.push(fields(&record));
+			}
+		}
+		Ok(rows)
+	}
+}
+
+impl Drop for CsvSource {
+	fn drop(&mut self) {
+		self.cancel();
+	}
+}
+
+fn build_index(file: File, shared: &Mutex<Index>, cancel: &AtomicBool) {
+	let mut rdr = ReaderBuilder::new()
+		.has_headers(false)
+		.flexible(true)
+		.from_reader(BufReader::with_capacity(1 << 20, file));
+	let mut record = ByteRecord::new();
+	let mut offsets = Vec::new();
+	let mut rows = 0;
+	let mut width = 0;
+	let (mut invalid, mut first_invalid_line) = (0, None);
+	let publish = |offsets: &mut Vec<u64>, rows, width, bytes, invalid, first, done| {
+		let mut index = lock(shared);
+		index.offsets.append(offsets);
+		index.rows = rows;
+		index.width = width;
+		index.bytes = bytes;
+		index.invalid = invalid;
+		index.first_invalid_line = first;
+		index.done = done;
+	};
+	loop {
+		if cancel.load(Ordering::Relaxed) {
+			debug!("Cancelled indexing after {rows} rows");
+			lock(shared).error = Some(format!("Cancelled after {rows} rows"));
+			break;
+		}
+		match rdr.read_byte_record(&mut record) {
+			Ok(true) => {}
+			Ok(false) => break,
+			Err(e) => {
+				lock(shared).error = Some(e.to_string());
+				break;
+			}
+		}
+		if rows % STRIDE == 0 {
+			offsets.push(record.position().map_or(0, |p| p.byte()));
+		}
+		rows += 1;
+		width = width.max(record.len());
+		let (count, first) = invalid_utf8(&record);
+		invalid += count;
+		first_invalid_line = first_invalid_line.or(first);
+		if rows % BATCH == 0 {
+			let bytes = rdr.position().byte();
+			publish(
+				&mut offsets,
+				rows,
+				width,
+				bytes,
+				invalid,
+				first_invalid_line,
+				false,
+			);
+		}
+	}
+	let bytes = rdr.position().byte();
+	publish(
+		&mut offsets,
+		rows,
+		width,
+		bytes,
+		invalid,
+		first_invalid_line,
+		true,
+	);
+	debug!("Indexed {rows} rows");
+}
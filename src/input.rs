@@ -1,11 +1,101 @@
-use std::fmt::{Debug, Display};
+//! Keys and sequences of keys, written like `<C-s>`, `<A-+> r`, or `g g`.
+//!
+//! A key is a character, or `<Name>` for keys like `<Tab>`, `<F12>`, `<Space>` and `<lt>` (`<`).
+//! Modifiers go in the brackets before it: `C-` for Ctrl, `A-` for Alt, `S-` for Shift, and `D-`,
+//! `H-` and `M-` for Super, Hyper and Meta. Shifted characters are written as what they type,
+//! so `A` rather than `<S-a>`. Keys in a sequence are separated by spaces.
+use std::{
+	fmt::{Debug, Display},
+	str::FromStr,
+};
 
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
-use serde::{Deserialize, Serialize};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MediaKeyCode, ModifierKeyCode};
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Hash, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct Input(pub KeyCode, pub KeyModifiers);
 
+/// Letters of modifiers, in the order they're written
+const MODIFIERS: [(KeyModifiers, char); 6] = [
+	(KeyModifiers::CONTROL, 'C'),
+	(KeyModifiers::ALT, 'A'),
+	(KeyModifiers::SHIFT, 'S'),
+	(KeyModifiers::SUPER, 'D'),
+	(KeyModifiers::HYPER, 'H'),
+	(KeyModifiers::META, 'M'),
+];
+
+/// Keys written by name, besides `F1`-`F255`
+const NAMED: [(KeyCode, &str); 52] = {
+	use KeyCode::*;
+	use MediaKeyCode as Md;
+	use ModifierKeyCode as Mod;
+	[
+		(Char(' '), "Space"),
+		(Char('<'), "lt"),
+		(Backspace, "Backspace"),
+		(Enter, "Enter"),
+		(Left, "Left"),
+		(Right, "Right"),
+		(Up, "Up"),
+		(Down, "Down"),
+		(Home, "Home"),
+		(End, "End"),
+		(PageUp, "PageUp"),
+		(PageDown, "PageDown"),
+		(Tab, "Tab"),
+		(BackTab, "BackTab"),
+		(Delete, "Delete"),
+		(Insert, "Insert"),
+		(Null, "Null"),
+		(Esc, "Esc"),
+		(CapsLock, "CapsLock"),
+		(ScrollLock, "ScrollLock"),
+		(NumLock, "NumLock"),
+		(PrintScreen, "PrintScreen"),
+		(Pause, "Pause"),
+		(Menu, "Menu"),
+		(KeypadBegin, "KeypadBegin"),
+		(Media(Md::Play), "MediaPlay"),
+		(Media(Md::Pause), "MediaPause"),
+		(Media(Md::PlayPause), "MediaPlayPause"),
+		(Media(Md::Reverse), "MediaReverse"),
+		(Media(Md::Stop), "MediaStop"),
+		(Media(Md::FastForward), "MediaFastForward"),
+		(Media(Md::Rewind), "MediaRewind"),
+		(Media(Md::TrackNext), "MediaTrackNext"),
+		(Media(Md::TrackPrevious), "MediaTrackPrevious"),
+		(Media(Md::Record), "MediaRecord"),
+		(Media(Md::LowerVolume), "MediaLowerVolume"),
+		(Media(Md::RaiseVolume), "MediaRaiseVolume"),
+		(Media(Md::MuteVolume), "MediaMuteVolume"),
+		(Modifier(Mod::LeftShift), "LeftShift"),
+		(Modifier(Mod::LeftControl), "LeftControl"),
+		(Modifier(Mod::LeftAlt), "LeftAlt"),
+		(Modifier(Mod::LeftSuper), "LeftSuper"),
+		(Modifier(Mod::LeftHyper), "LeftHyper"),
+		(Modifier(Mod::LeftMeta), "LeftMeta"),
+		(Modifier(Mod::RightShift), "RightShift"),
+		(Modifier(Mod::RightControl), "RightControl"),
+		(Modifier(Mod::RightAlt), "RightAlt"),
+		(Modifier(Mod::RightSuper), "RightSuper"),
+		(Modifier(Mod::RightHyper), "RightHyper"),
+		(Modifier(Mod::RightMeta), "RightMeta"),
+		(Modifier(Mod::IsoLevel3Shift), "IsoLevel3Shift"),
+		(Modifier(Mod::IsoLevel5Shift), "IsoLevel5Shift"),
+	]
+};
+
+/// Other names accepted for keys
+const ALIASES: [(KeyCode, &str); 6] = [
+	(KeyCode::Enter, "CR"),
+	(KeyCode::Enter, "Return"),
+	(KeyCode::Esc, "Escape"),
+	(KeyCode::Backspace, "BS"),
+	(KeyCode::Delete, "Del"),
+	(KeyCode::Insert, "Ins"),
+];
+
 impl Ord for Input {
 	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
 		self.0
@@ -19,13 +109,24 @@ impl Ord for Input {
 	}
 }
 
+impl PartialOrd for Input {
+	fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
 impl From<KeyEvent> for Input {
 	fn from(
 		KeyEvent {
 			code, modifiers, ..
 		}: KeyEvent,
 	) -> Self {
-		Self(code, modifiers)
+		match code {
+			// the character is already shifted
+			KeyCode::Char(_) => Self(code, modifiers - KeyModifiers::SHIFT),
+			KeyCode::BackTab => Self(KeyCode::Tab, modifiers | KeyModifiers::SHIFT),
+			_ => Self(code, modifiers),
+		}
 	}
 }
 
@@ -37,7 +138,6 @@ impl From<KeyCode> for Input {
 
 impl From<char> for Input {
 	fn from(c: char) -> Self {
-		// TODO: should this be modifier shift for shift?
 		Self(KeyCode::Char(c), KeyModifiers::NONE)
 	}
 }
@@ -46,45 +146,157 @@ impl Display for Input {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		let Self(code, modifiers) = self;
 
-		// TODO: handle other modifiers?
-		if modifiers.contains(KeyModifiers::CONTROL) {
-			write!(f, "Ctrl ")?;
+		let name = NAMED.iter().find(|(k, _)| k == code).map(|(_, name)| name);
+		if let (KeyCode::Char(c), None) = (code, name) {
+			if modifiers.is_empty() {
+				return write!(f, "{c}");
+			}
 		}
-		if modifiers.contains(KeyModifiers::ALT) {
-			write!(f, "Alt ")?;
+
+		write!(f, "<")?;
+		for (modifier, letter) in MODIFIERS {
+			if modifiers.contains(modifier) {
+				write!(f, "{letter}-")?;
+			}
 		}
-		if modifiers.contains(KeyModifiers::SHIFT) {
-			write!(f, "Shift ")?;
+		match (code, name) {
+			(_, Some(name)) => write!(f, "{name}")?,
+			(KeyCode::F(num), _) => write!(f, "F{num}")?,
+			(KeyCode::Char(c), _) => write!(f, "{c}")?,
+			_ => write!(f, "{code:?}")?,
 		}
+		write!(f, ">")
+	}
+}
 
-		match code {
-			KeyCode::F(num) => write!(f, "F{num}")?,
-			KeyCode::Char(c) => write!(f, "{}", c)?,
-			_ => write!(f, "{:?}", code)?,
+/// A key that isn't written correctly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseInputError(String);
+
+impl Display for ParseInputError {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		write!(f, "unknown key `{}`", self.0)
+	}
+}
+
+impl std::error::Error for ParseInputError {}
+
+impl FromStr for Input {
+	type Err = ParseInputError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let err = || ParseInputError(s.to_string());
+		let mut chars = s.chars();
+		if let (Some(c), None) = (chars.next(), chars.next()) {
+			return Ok(Self(KeyCode::Char(c), KeyModifiers::NONE));
 		}
 
-		Ok(())
+		let mut name = s
+			.strip_prefix('<')
+			.and_then(|s| s.strip_suffix('>'))
+			.ok_or_else(err)?;
+		let mut modifiers = KeyModifiers::NONE;
+		while let Some((modifier, rest)) = name
+			.split_once('-')
+			.filter(|(m, r)| m.len() == 1 && !r.is_empty())
+		{
+			let (m, _) = MODIFIERS
+				.iter()
+				.find(|(_, l)| modifier.eq_ignore_ascii_case(&l.to_string()))
+				.ok_or_else(err)?;
+			modifiers |= *m;
+			name = rest;
+		}
+
+		let mut chars = name.chars();
+		let code = if let (Some(c), None) = (chars.next(), chars.next()) {
+			KeyCode::Char(c)
+		} else if let Some(num) = name
+			.strip_prefix(['F', 'f'])
+			.and_then(|n| n.parse().ok())
+			.filter(|&n| n > 0)
+		{
+			KeyCode::F(num)
+		} else {
+			NAMED
+				.iter()
+				.chain(&ALIASES)
+				.find(|(_, n)| n.eq_ignore_ascii_case(name))
+				.ok_or_else(err)?
+				.0
+		};
+		Ok(Self(code, modifiers))
 	}
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
-pub struct InputBuffer(Vec<Input>);
+impl Serialize for Input {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+impl<'de> Deserialize<'de> for Input {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		String::deserialize(deserializer)?
+			.parse()
+			.map_err(de::Error::custom)
+	}
+}
+
+/// Largest count that can be typed before an action
+const MAX_COUNT: usize = 99_999;
+
+/// Keys typed towards a binding, and the count typed before them
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InputBuffer {
+	keys: Vec<Input>,
+	/// Digits of the count
+	count: String,
+}
 
 impl InputBuffer {
+	/// Whether no keys have been typed, though there may be a count
 	pub fn is_empty(&self) -> bool {
-		self.0.is_empty()
+		self.keys.is_empty()
 	}
 
 	pub fn push(&mut self, i: Input) {
-		self.0.push(i);
+		self.keys.push(i);
 	}
 
 	pub fn pop(&mut self) -> Option<Input> {
-		self.0.pop()
+		self.keys.pop()
+	}
+
+	/// Whether keys or a count have been typed, waiting for the rest of a binding
+	pub fn is_pending(&self) -> bool {
+		!self.keys.is_empty() || !self.count.is_empty()
 	}
 
+	/// Number of keys typed, including the digits of the count
+	pub fn typed(&self) -> usize {
+		self.keys.len() + self.count.chars().count()
+	}
+
+	/// Add a digit to the end of the count.
+	pub fn push_digit(&mut self, digit: char) {
+		self.count.push(digit);
+	}
+
+	/// Number typed before the keys, if any
+	pub fn count(&self) -> Option<usize> {
+		(!self.count.is_empty()).then(|| self.count.parse().unwrap_or(MAX_COUNT).min(MAX_COUNT))
+	}
+
+	/// Digits typed before the keys, as they were typed
+	pub fn digits(&self) -> &str {
+		&self.count
+	}
+
+	/// Forget the keys and count.
 	pub fn clear(&mut self) {
-		self.0.drain(..);
+		self.keys.clear();
+		self.count.clear();
 	}
 }
 
@@ -94,23 +306,23 @@ impl<'a> IntoIterator for &'a InputBuffer {
 	type IntoIter = std::slice::Iter<'a, Input>;
 
 	fn into_iter(self) -> Self::IntoIter {
-		self.0.iter()
+		self.keys.iter()
 	}
 }
 
-impl<'a> IntoIterator for InputBuffer {
+impl IntoIterator for InputBuffer {
 	type Item = Input;
 
 	type IntoIter = std::vec::IntoIter<Input>;
 
 	fn into_iter(self) -> Self::IntoIter {
-		self.0.into_iter()
+		self.keys.into_iter()
 	}
 }
 
 impl Extend<Input> for InputBuffer {
 	fn extend<T: IntoIterator<Item = Input>>(&mut self, iter: T) {
-		self.0.extend(iter);
+		self.keys.extend(iter);
 	}
 }
 
@@ -126,10 +338,107 @@ impl Display for InputBuffer {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 		for (i, input) in self.into_iter().enumerate() {
 			if i != 0 {
-				write!(f, ", ")?;
+				write!(f, " ")?;
 			}
 			write!(f, "{}", input)?;
 		}
 		Ok(())
 	}
 }
+
+impl FromStr for InputBuffer {
+	type Err = ParseInputError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		s.split_whitespace().map(str::parse).collect()
+	}
+}
+
+impl Serialize for InputBuffer {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+impl<'de> Deserialize<'de> for InputBuffer {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		String::deserialize(deserializer)?
+			.parse()
+			.map_err(de::Error::custom)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// One of every kind of key
+	fn every_code() -> Vec<KeyCode> {
+		use KeyCode::*;
+		let mut codes = vec![
+			Char('a'),
+			Char('+'),
+			Char('-'),
+			Char('>'),
+			Char('é'),
+			F(1),
+			F(12),
+		];
+		codes.extend(NAMED.iter().map(|(k, _)| *k));
+		for code in &codes {
+			// fails to compile when a variant is added, so it can be named too
+			match code {
+				Backspace | Enter | Left | Right | Up | Down | Home | End | PageUp | PageDown
+				| Tab | BackTab | Delete | Insert | F(_) | Char(_) | Null | Esc | CapsLock
+				| ScrollLock | NumLock | PrintScreen | Pause | Menu | KeypadBegin | Media(_)
+				| Modifier(_) => {}
+			}
+		}
+		codes
+	}
+
+	#[test]
+	fn roundtrips_every_key() {
+		for code in every_code() {
+			for modifiers in [
+				KeyModifiers::NONE,
+				KeyModifiers::CONTROL,
+				KeyModifiers::ALT | KeyModifiers::SHIFT,
+				KeyModifiers::all(),
+			] {
+				let input = Input(code, modifiers);
+				let s = input.to_string();
+				assert_eq!(Ok(input), s.parse(), "{s}");
+			}
+		}
+	}
+
+	#[test]
+	fn parses_notation() {
+		use KeyCode::*;
+		let ctrl = KeyModifiers::CONTROL;
+		let alt = KeyModifiers::ALT;
+		let cases = [
+			("<C-s>", Input(Char('s'), ctrl)),
+			("<A-+>", Input(Char('+'), alt)),
+			("<A-->", Input(Char('-'), alt)),
+			("<S-Tab>", Input(Tab, KeyModifiers::SHIFT)),
+			("<F12>", Input(F(12), KeyModifiers::NONE)),
+			("<c-a-Space>", Input(Char(' '), ctrl | alt)),
+			("<cr>", Input(Enter, KeyModifiers::NONE)),
+			("<", Input(Char('<'), KeyModifiers::NONE)),
+			("G", Input(Char('G'), KeyModifiers::NONE)),
+		];
+		for (s, input) in cases {
+			assert_eq!(Ok(input), s.parse(), "{s}");
+		}
+		for s in ["", "ab", "<Foo>", "<X-a>", "<C->", "<F0>", "<C-s"] {
+			assert!(s.parse::<Input>().is_err(), "{s}");
+		}
+
+		let g = Input(Char('g'), KeyModifiers::NONE);
+		assert_eq!(Ok(InputBuffer::from_iter([g, g])), "g  g".parse());
+		let buf: InputBuffer = "<A-+> r".parse().unwrap();
+		assert_eq!("<A-+> r", buf.to_string());
+	}
+}
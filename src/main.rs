@@ -1,14 +1,20 @@
 //! A "simple" and straightforward terminal spreadsheet editor, in the spirit of nano and htop.
 // TODO: handle different formats ala xsv
-// TODO: unify bindings
 // TODO: online help system
 // TODO: interrupt handling
 // TODO: view state in debug view
 // TODO: draw frozen column/row numbers
 // TODO: freeze header
-// TODO: copy/paste
-// TODO: extend binding to include mode switching, counts, type-to-edit cell
-use std::{env, error::Error, fs::File, io, panic, path::PathBuf, sync::Mutex, time};
+use std::{
+	env,
+	error::Error,
+	fs::{File, OpenOptions},
+	io::{self, Write},
+	panic,
+	path::PathBuf,
+	sync::Mutex,
+	time,
+};
 
 use crossterm::{
 	cursor::{self, SetCursorStyle},
@@ -26,14 +32,26 @@ use tui::{
 	Terminal,
 };
 
-use crate::program::ExternalAction;
+use crate::{
+	encoding::TextEncoding,
+	format::{fixed::Columns, Format},
+	program::{ExternalAction, FileOptions},
+};
 
 mod bindings;
+mod clipboard;
+mod compression;
+mod config;
+mod encoding;
+mod format;
 mod grid;
 mod input;
 mod logger;
 mod program;
+mod swap;
+mod task;
 mod views;
+mod watch;
 
 use grid::Grid;
 use program::Program;
@@ -45,6 +63,12 @@ mod styles {
 		Style::default().add_modifier(Modifier::REVERSED)
 	}
 
+	pub fn selected_range() -> Style {
+		Style::default()
+			.add_modifier(Modifier::REVERSED)
+			.add_modifier(Modifier::DIM)
+	}
+
 	pub fn grid() -> Style {
 		Style::default().add_modifier(Modifier::UNDERLINED)
 	}
@@ -60,8 +84,23 @@ mod styles {
 
 #[derive(Debug, StructOpt)]
 struct Opt {
-	#[structopt(parse(from_os_str))]
-	file: PathBuf,
+	/// Files to edit, each in its own buffer. `-` reads from stdin and writes to stdout on exit,
+	/// and `data.db:table` edits a SQLite table.
+	#[structopt(parse(from_os_str), required = true)]
+	files: Vec<PathBuf>,
+	/// Text encoding of the file, e.g. `latin1` or `utf-16be`. Detected if not provided.
+	#[structopt(short, long)]
+	encoding: Option<TextEncoding>,
+	/// Format of the file: `csv`, `json`, `ndjson`, or `fixed`. Guessed from the extension if not provided.
+	#[structopt(short, long)]
+	format: Option<Format>,
+	/// Columns of a fixed-width file as 1-based `start:length` pairs, e.g. `1:8,10:3`.
+	/// Inferred from a ruler line of dashes under the header if not provided.
+	#[structopt(short, long)]
+	columns: Option<Columns>,
+	/// Add rows appended to the file while it's open, like `tail -f`
+	#[structopt(long)]
+	follow: bool,
 }
 
 #[derive(Debug, Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -78,6 +117,15 @@ pub struct Rect<T> {
 	pub height: T,
 }
 
+impl<T> Rect<T>
+where
+	T: Copy + PartialOrd + std::ops::Add<Output = T>,
+{
+	pub fn contains(&self, p: XY<T>) -> bool {
+		p.x >= self.x && p.x < self.x + self.width && p.y >= self.y && p.y < self.y + self.height
+	}
+}
+
 impl<T> TryInto<tui::layout::Rect> for Rect<T>
 where
 	T: TryInto<u16>,
@@ -122,25 +170,33 @@ where
 	}
 }
 
+/// Output for drawing the UI, using the controlling terminal if stdout is redirected.
+fn terminal_output() -> io::Result<Box<dyn Write>> {
+	if atty::is(atty::Stream::Stdout) {
+		return Ok(Box::new(io::stdout()));
+	}
+	Ok(Box::new(OpenOptions::new().write(true).open("/dev/tty")?))
+}
+
 fn setup_terminal() -> io::Result<Terminal<impl Backend>> {
 	enable_raw_mode()?;
-	let mut stdout = io::stdout();
+	let mut out = terminal_output()?;
 	execute!(
-		stdout,
+		out,
 		EnterAlternateScreen,
 		SetCursorStyle::BlinkingBlock,
 		// EnableMouseCapture
 	)?;
-	let backend = CrosstermBackend::new(stdout);
+	let backend = CrosstermBackend::new(out);
 	Terminal::new(backend)
 }
 
 fn teardown_terminal() -> io::Result<()> {
 	// restore terminal
 	disable_raw_mode()?;
-	let mut stdout = io::stdout();
+	let mut out = terminal_output()?;
 	execute!(
-		stdout,
+		out,
 		LeaveAlternateScreen,
 		SetCursorStyle::DefaultUserShape,
 		// DisableMouseCapture,
@@ -159,18 +215,26 @@ fn main() -> Result<(), Box<dyn Error>> {
 		serde_json::from_reader(f)?
 	} else {
 		let opt = Opt::from_args();
-		Program::from_path(opt.file)?
+		let options = FileOptions {
+			encoding: opt.encoding,
+			format: opt.format,
+			columns: opt.columns,
+			follow: opt.follow,
+		};
+		let mut program = Program::from_paths(opt.files, options)?;
+		program.load_config();
+		program
 	};
 
 	let program = Mutex::new(program);
 
 	match panic::catch_unwind(|| {
 		let mut program = program.lock().unwrap();
-		run(&mut *program)
+		run(&mut program)
 	}) {
 		Ok(r) => r?,
 		Err(panic) => {
-			let program = program.lock().map_or_else(|e| e.into_inner(), |l| l);
+			let program = program.lock().unwrap_or_else(|e| e.into_inner());
 			match write_state_to_temp(&program) {
 				Ok(path) => eprintln!("Captured program state at {path:?}"),
 				Err(e) => eprintln!("Error writing captured state: {e}"),
@@ -182,6 +246,11 @@ fn main() -> Result<(), Box<dyn Error>> {
 	info!("Stopping");
 	teardown_terminal()?;
 
+	let program = program.into_inner().unwrap_or_else(|e| e.into_inner());
+	if let Some(output) = program.stdout() {
+		io::stdout().write_all(output)?;
+	}
+
 	Ok(())
 }
 
@@ -201,6 +270,13 @@ fn run(program: &mut Program) -> Result<(), Box<dyn Error>> {
 	program.draw(terminal)?;
 
 	loop {
+		if !event::poll(program.tick_interval())? {
+			program.tick();
+			if program.should_redraw {
+				program.draw(terminal)?;
+			}
+			continue;
+		}
 		let event = event::read()?;
 		trace!("New event: {event:?}");
 		let k = match event {
@@ -220,6 +296,7 @@ fn run(program: &mut Program) -> Result<(), Box<dyn Error>> {
 				ExternalAction::Quit => break,
 			}
 		}
+		program.tick();
 
 		if program.should_redraw {
 			program.draw(terminal)?;
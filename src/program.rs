@@ -1,11 +1,16 @@
 use std::{
 	cmp::min,
+	collections::BTreeMap,
 	fmt::Display,
-	io,
+	fs::{self, File},
+	io::{self, Read as _, Seek as _, SeekFrom, Write as _},
+	mem,
 	ops::ControlFlow,
 	path::{Path, PathBuf},
+	time::{Duration, Instant},
 };
 
+use crossterm::event::{KeyCode, KeyModifiers};
 use serde::{Deserialize, Serialize};
 use tui::{
 	backend::Backend,
@@ -17,18 +22,34 @@ use tui::{
 };
 
 use crate::{
-	bindings::{BindNode, Bindings},
-	grid::{ChangeTracker, Grid},
+	bindings::{BindNode, Bindings, Conflict},
+	clipboard,
+	compression::Compression,
+	config::{self, Config, Macros},
+	encoding::TextEncoding,
+	format::{
+		fixed::{self, Columns},
+		json,
+		spreadsheet::{self, Workbook},
+		sql, Excerpt, Format, TableFormat,
+	},
+	grid::{Change, ChangeTracker, Grid, Progress},
 	input::{Input, InputBuffer},
 	styles,
+	swap::{self, Swap},
+	task::{self, Counted, Counter, Task, Unit},
 	views::{
-		DebugView, Dialog, EditState, EditView, GridState, GridView, PaletteState, PaletteView,
+		DebugView, Dialog, EditAction, EditState, EditView, GridState, GridView, InputState,
+		InputView, PaletteState, PaletteView, PromptState, PromptView, DEFAULT_WIDTH,
 	},
+	watch::{Stamp, Watcher},
 	Rect as MyRect, XY,
 };
 
 mod action;
 pub use action::*;
+mod split;
+use split::Split;
 
 #[derive(Debug, Serialize, Deserialize)]
 enum Status {
@@ -43,6 +64,57 @@ enum Status {
 	UndoLimit,
 	RedoLimit,
 	DumpState(#[serde(skip, default = "default_io_result")] io::Result<PathBuf>),
+	Swap(
+		PathBuf,
+		#[serde(skip, default = "default_io_result")] io::Result<()>,
+	),
+	Recover(
+		usize,
+		#[serde(skip, default = "default_io_result")] io::Result<()>,
+	),
+	/// Bytes that couldn't be decoded were replaced
+	Decode {
+		encoding: String,
+		errors: usize,
+		first_line: usize,
+	},
+	/// Characters that couldn't be encoded were replaced
+	Encode {
+		encoding: String,
+		unmappable: usize,
+	},
+	/// Cells were truncated to fit fixed-width columns
+	Overflow {
+		cells: usize,
+		first: XY<usize>,
+	},
+	Export(
+		PathBuf,
+		#[serde(skip, default = "default_io_result")] io::Result<()>,
+	),
+	Copy(#[serde(skip, default = "default_io_result")] io::Result<()>),
+	/// Whether rows appended to the file are being added
+	Follow(#[serde(skip, default = "default_io_result")] io::Result<bool>),
+	/// Rows appended to the file were added
+	Appended(
+		usize,
+		#[serde(skip, default = "default_io_result")] io::Result<()>,
+	),
+	Config(
+		PathBuf,
+		#[serde(skip, default = "default_io_result")] io::Result<()>,
+	),
+	/// Waiting for the key naming the register of a macro to record or play
+	PickRegister,
+	Recording(char),
+	/// A macro was recorded, and saved if it could be
+	Recorded(
+		char,
+		#[serde(skip, default = "default_io_result")] io::Result<()>,
+	),
+	NoMacro(char),
+	/// What was typed for an action's argument can't be used
+	Argument(String),
 }
 
 fn default_io_result<T: Default>() -> io::Result<T> {
@@ -55,12 +127,27 @@ impl Status {
 			Status::Read(.., Err(e)) => e,
 			Status::Write(.., Err(e)) => e,
 			Status::DumpState(Err(e)) => e,
+			Status::Swap(.., Err(e)) => e,
+			Status::Recover(.., Err(e)) => e,
+			Status::Export(.., Err(e)) => e,
+			Status::Copy(Err(e)) => e,
+			Status::Follow(Err(e)) => e,
+			Status::Appended(.., Err(e)) => e,
+			Status::Config(.., Err(e)) => e,
+			Status::Recorded(.., Err(e)) => e,
 			_ => return None,
 		})
 	}
 
 	fn is_err(&self) -> bool {
 		self.err().is_some()
+			|| matches!(
+				self,
+				Status::Decode { .. }
+					| Status::Encode { .. }
+					| Status::Overflow { .. }
+					| Status::Argument(_)
+			)
 	}
 }
 
@@ -75,17 +162,63 @@ impl Display for Status {
 			Status::RedoLimit => write!(f, "Nothing left to redo")?,
 			Status::DumpState(Ok(p)) => write!(f, "Dumped state to {p:?}")?,
 			Status::DumpState(Err(e)) => write!(f, "Error dumping state: {e}")?,
+			Status::Swap(p, Ok(())) => write!(f, "Updated swap file {p:?}")?,
+			Status::Swap(p, Err(e)) => write!(f, "Error updating swap file {p:?}: {e}")?,
+			Status::Export(p, Ok(())) => write!(f, "Exported table to {p:?}")?,
+			Status::Export(p, Err(e)) => write!(f, "Error exporting to {p:?}: {e}")?,
+			Status::Copy(Ok(())) => write!(f, "Copied table to clipboard")?,
+			Status::Copy(Err(e)) => write!(f, "Error copying to clipboard: {e}")?,
+			Status::Recover(n, Ok(())) => write!(f, "Recovered {n} changes from swap file")?,
+			Status::Recover(_, Err(e)) => {
+				write!(f, "Error recovering changes from swap file: {e}")?
+			}
+			Status::Follow(Ok(true)) => write!(f, "Following the file for new rows")?,
+			Status::Follow(Ok(false)) => write!(f, "Stopped following the file")?,
+			Status::Follow(Err(e)) => write!(f, "Can't follow the file: {e}")?,
+			Status::Appended(n, Ok(())) => write!(f, "Added {n} new rows from the file")?,
+			Status::Appended(_, Err(e)) => write!(f, "Error reading new rows: {e}")?,
+			Status::Config(p, Ok(())) => write!(f, "Read config from {p:?}")?,
+			Status::Config(p, Err(e)) => write!(f, "Error in config {p:?}, ignoring it: {e}")?,
+			Status::PickRegister => write!(f, "Name the macro's register with a letter or digit")?,
+			Status::Recording(c) => write!(f, "Recording macro @{c}")?,
+			Status::Recorded(c, Ok(())) => write!(f, "Recorded macro @{c}")?,
+			Status::Recorded(c, Err(e)) => {
+				write!(f, "Recorded macro @{c}, but can't save it: {e}")?
+			}
+			Status::NoMacro(c) => write!(f, "No macro @{c}")?,
+			Status::Argument(msg) => write!(f, "{msg}")?,
+			Status::Decode {
+				encoding,
+				errors,
+				first_line,
+			} => write!(
+				f,
+				"Replaced {errors} invalid {encoding} byte sequences (first on line {first_line})"
+			)?,
+			Status::Encode {
+				encoding,
+				unmappable,
+			} => write!(
+				f,
+				"Replaced {unmappable} characters that can't be written as {encoding} with '?'"
+			)?,
+			Status::Overflow { cells, first } => write!(
+				f,
+				"Truncated {cells} cells too wide for their columns (first at {},{})",
+				first.x + 1,
+				first.y + 1
+			)?,
 		}
 		Ok(())
 	}
 }
 
-impl<'s, 't> Into<Text<'t>> for &'s Status {
-	fn into(self) -> Text<'t> {
+impl<'t> From<&Status> for Text<'t> {
+	fn from(status: &Status) -> Self {
 		let base = Style::default().add_modifier(Modifier::ITALIC);
 		Text::styled(
-			self.to_string(),
-			if self.is_err() {
+			status.to_string(),
+			if status.is_err() {
 				base.patch(styles::error())
 			} else {
 				base
@@ -94,42 +227,1419 @@ impl<'s, 't> Into<Text<'t>> for &'s Status {
 	}
 }
 
+/// How to interpret the file being edited
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct FileOptions {
+	/// Text encoding to use instead of detecting it
+	pub encoding: Option<TextEncoding>,
+	/// File format to use instead of guessing from the extension
+	pub format: Option<Format>,
+	/// Columns of a fixed-width file, instead of inferring them from a ruler
+	pub columns: Option<Columns>,
+	/// Add rows appended to the file while it's open, like `tail -f`
+	pub follow: bool,
+}
+
 #[derive(Default, Debug, Serialize, Deserialize)]
 pub struct Program {
 	view: ViewState,
-	grid: Grid,
-	grid_state: GridState,
-	change_tracker: ChangeTracker,
-	filename: PathBuf,
+	/// Buffer being shown and edited, taken out of `buffers`
+	buf: Buffer,
+	/// Every open buffer, with an empty placeholder at `active`
+	buffers: Vec<Buffer>,
+	/// Index of `buf` in `buffers`
+	active: usize,
+	/// Every pane, with the position of the focused one kept in `buf` instead
+	panes: Vec<Pane>,
+	/// Index of the pane showing `buf`, which takes input
+	focus: usize,
+	/// Arrangement of `panes` on the screen
+	split: Split,
+	/// Whether moving in the focused pane moves the others too
+	sync_scroll: bool,
 	/// Store chorded keys
 	input_buf: InputBuffer,
-	selection: XY<usize>,
+	/// How long a partly typed chord waits for the next key before it's dropped, if at all
+	chord_timeout: Option<Duration>,
+	#[serde(skip)]
+	last_input: Option<Instant>,
 	/// Stored for movements based on screen size
 	last_visible_grid_cells: XY<usize>,
 	bindings: Bindings<Action>,
+	/// Vim-style bindings used instead of `bindings`, if enabled
+	vim: Option<Vim>,
+	/// Rows or cells copied or deleted, to be pasted
+	register: Option<Register>,
+	/// Last change made, to make again
+	last_change: Option<LastChange>,
+	/// Keys recorded into registers, to play back
+	macros: Macros,
+	/// Macro being recorded
+	recording: Option<Recording>,
+	/// What to do with the register named by the next key
+	macro_step: Option<MacroStep>,
+	/// Number of macros being played, by other macros
+	#[serde(skip)]
+	playing: usize,
+	/// Default bindings replaced by ones from the config
+	#[serde(skip)]
+	conflicts: Vec<Conflict<Action>>,
+	/// Settings from the config, kept to merge its bindings over the vim-style ones when they're
+	/// enabled
+	#[serde(skip)]
+	config: Config,
 	pub should_redraw: bool,
 	/// Result of latest action to display to user
 	status_msg: Option<Status>,
 }
 
-impl Program {
-	pub fn from_path(filename: impl AsRef<Path>) -> io::Result<Self> {
-		let filename = filename.as_ref().to_path_buf();
+/// Vim-style modal key bindings
+#[derive(Debug, Serialize, Deserialize)]
+struct Vim {
+	normal: Bindings<Action>,
+	/// Bindings while selecting a range
+	visual: Bindings<Action>,
+	/// Vim-style bindings replaced by ones from the config
+	#[serde(skip)]
+	conflicts: Vec<Conflict<Action>>,
+}
+
+impl Vim {
+	/// Vim-style bindings with the config's bindings merged over them
+	fn new(config: &Config) -> Self {
+		let mut normal = Bindings::vim();
+		let mut visual = Bindings::vim_visual();
+		let mut conflicts = config.apply(&mut normal);
+		for conflict in config.apply(&mut visual) {
+			if !conflicts.contains(&conflict) {
+				conflicts.push(conflict);
+			}
+		}
+		Self {
+			normal,
+			visual,
+			conflicts,
+		}
+	}
+
+	fn bindings(&self, visual: bool) -> &Bindings<Action> {
+		if visual {
+			&self.visual
+		} else {
+			&self.normal
+		}
+	}
+}
+
+/// Mode of vim-style key bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VimMode {
+	Normal,
+	/// Editing a cell
+	Insert,
+	/// Selecting a range
+	Visual,
+}
+
+/// Cells that were copied or deleted
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Register {
+	/// Whole rows, pasted as new rows
+	Rows(Vec<Vec<String>>),
+	/// A range of cells, pasted over the cells at the cursor
+	Cells(Vec<Vec<String>>),
+}
+
+/// Change to make again by repeating it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum LastChange {
+	/// An action, with the count it was done with
+	Action(Action, Option<usize>),
+	/// Setting a cell to what was typed into it
+	Edit(String),
+}
+
+/// Keys being recorded into a register
+#[derive(Debug, Serialize, Deserialize)]
+struct Recording {
+	register: char,
+	keys: Vec<Input>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum MacroStep {
+	Record,
+	/// Play the macro this many times
+	Play(usize),
+}
+
+/// Part of the screen showing a buffer
+#[derive(Default, Debug, Serialize, Deserialize)]
+struct Pane {
+	/// Index of the buffer in `Program::buffers`
+	buffer: usize,
+	/// Position in the buffer, while another pane is focused
+	selection: XY<usize>,
+	anchor: Option<XY<usize>>,
+	grid_state: GridState,
+}
+
+/// An open file and the sheet being edited from it
+#[derive(Default, Debug, Serialize, Deserialize)]
+struct Buffer {
+	grid: Grid,
+	grid_state: GridState,
+	change_tracker: ChangeTracker,
+	filename: PathBuf,
+	options: FileOptions,
+	/// Compression of the file, detected on read and reused on write
+	compression: Compression,
+	/// Text encoding of the file, detected on read and reused on write
+	encoding: TextEncoding,
+	format: Format,
+	/// Columns of a fixed-width file, found on read and reused on write
+	layout: Option<fixed::Layout>,
+	/// Types of the values of a JSON file, found on read and reused on write
+	types: json::Types,
+	/// Database table being edited instead of a file, if any
+	table: Option<sql::Table>,
+	/// Worksheet being viewed, if any, which is saved to a different file since workbooks can't be written
+	workbook: Option<Workbook>,
+	selection: XY<usize>,
+	/// Opposite corner of the selected range from `selection`, if selecting
+	anchor: Option<XY<usize>>,
+	/// Widths set for columns by index, instead of fitting their contents
+	widths: BTreeMap<usize, u16>,
+	/// `ChangeTracker` version last written to the swap file
+	#[serde(skip)]
+	swap_version: u64,
+	#[serde(skip)]
+	last_swap: Option<Instant>,
+	/// Contents of stdin, replaced on each write, if editing from a pipeline
+	#[serde(skip)]
+	stdio: Option<Vec<u8>>,
+	/// Whether the file backing the grid is still being indexed
+	#[serde(skip)]
+	indexing: bool,
+	#[serde(skip)]
+	job: Option<Job>,
+	/// Whether the file is still being opened, to check for a swap file once it's loaded
+	#[serde(skip)]
+	opening: bool,
+	/// Watches for other programs changing the file, if it's a file
+	#[serde(skip)]
+	watcher: Option<Watcher>,
+	/// The file as it was last read or written, to tell changes by other programs from ours
+	#[serde(skip)]
+	stamp: Option<Stamp>,
+	/// Whether the watcher saw a change that hasn't been dealt with yet
+	#[serde(skip)]
+	disk_changed: bool,
+	/// Whether to add rows appended to the file, like `tail -f`
+	follow: bool,
+}
+
+impl Buffer {
+	/// Whether the file is being read or written in the background
+	fn is_busy(&self) -> bool {
+		self.indexing || self.job.is_some()
+	}
+
+	fn name(&self) -> String {
+		match &self.stdio {
+			Some(_) => "stdin".to_string(),
+			None => self.filename.to_string_lossy().into_owned(),
+		}
+	}
+
+	/// Number of rows at the top naming the columns, which sorting leaves in place
+	fn header_rows(&self) -> usize {
+		match (&self.table, self.format, &self.layout) {
+			(Some(_), ..) | (None, Format::Json | Format::Ndjson, _) => 1,
+			(None, Format::Fixed, Some(layout)) => layout.header_rows(),
+			_ => 0,
+		}
+	}
+}
+
+/// Keep the widths set for columns with them after the change undone by `undo`, moving them past
+/// an inserted column or back over a deleted one.
+fn shift_widths(widths: &mut BTreeMap<usize, u16>, undo: &Change) {
+	match undo {
+		Change::InsertCol { col } => {
+			let moved = widths.split_off(col);
+			widths.extend(moved.into_iter().map(|(x, width)| (x + 1, width)));
+		}
+		Change::DeleteCol { col, .. } => {
+			let moved = widths.split_off(col);
+			widths.extend(
+				moved
+					.into_iter()
+					.filter(|&(x, _)| x != *col)
+					.map(|(x, width)| (x - 1, width)),
+			);
+		}
+		// undone last to first
+		Change::Batch(undos) => undos.iter().rev().for_each(|u| shift_widths(widths, u)),
+		Change::Replace { .. }
+		| Change::ReplaceGrid { .. }
+		| Change::DeleteRow { .. }
+		| Change::InsertRow { .. } => {}
+	}
+}
+
+/// Filename used to read from stdin and write to stdout
+pub const STDIO_PATH: &str = "-";
+
+/// CSV files at least this big are read as they're displayed instead of all at once
+const LAZY_SIZE: u64 = 64 << 20;
+/// Bytes read to check the encoding of a file before reading it lazily
+const LAZY_SAMPLE: u64 = 1 << 16;
+/// How often to update the progress of reads and writes in the background
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+/// How often to check for changes to the file by other programs
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+/// How many macros can be playing at once, playing each other
+const MAX_MACRO_DEPTH: usize = 8;
+
+/// Read or write of the file running in the background
+#[derive(Debug)]
+enum Job {
+	Read(Task<Loaded>),
+	/// Writing the grid as of `version` of the change tracker
+	Write {
+		task: Task<()>,
+		version: u64,
+	},
+}
+
+impl Job {
+	fn cancel(&self) {
+		match self {
+			Job::Read(task) => task.cancel(),
+			Job::Write { task, .. } => task.cancel(),
+		}
+	}
+}
+
+/// Contents of a file, decoded and parsed
+#[derive(Debug)]
+struct Loaded {
+	grid: Grid,
+	compression: Compression,
+	encoding: TextEncoding,
+	layout: Option<fixed::Layout>,
+	types: json::Types,
+	/// Problem decoding the file, if any
+	warning: Option<Status>,
+}
+
+impl Loaded {
+	fn decode(
+		bytes: Vec<u8>,
+		compression: Compression,
+		format: Format,
+		options: &FileOptions,
+	) -> io::Result<Self> {
+		let mut encoding = options
+			.encoding
+			.unwrap_or_else(|| TextEncoding::detect(&bytes));
+		let decoded = encoding.decode(&bytes);
+		debug!("Decoded {} bytes as {format} {encoding}", bytes.len());
+
+		let (grid, layout, types) = match format {
+			Format::Fixed => {
+				let layout = fixed::Layout::infer(&decoded.text, options.columns.as_ref())?;
+				(layout.read(&decoded.text), Some(layout), Default::default())
+			}
+			Format::Json => {
+				let (grid, types) = json::read(&decoded.text)?;
+				(grid, None, types)
+			}
+			Format::Ndjson => {
+				let (grid, types) = json::read_lines(&decoded.text)?;
+				(grid, None, types)
+			}
+			format => (format.read(&decoded.text)?, None, Default::default()),
+		};
+		let warning = decoded.errors.first().map(|&first| Status::Decode {
+			encoding: encoding.name().to_string(),
+			errors: decoded.errors.len(),
+			first_line: first,
+		});
+		Ok(Self {
+			grid,
+			compression,
+			encoding,
+			layout,
+			types,
+			warning,
+		})
+	}
+}
 
+impl Program {
+	/// Open each file in its own buffer, showing the first.
+	pub fn from_paths(filenames: Vec<PathBuf>, options: FileOptions) -> io::Result<Self> {
 		let mut s = Self {
+			buffers: vec![Buffer::default()],
+			panes: vec![Pane::default()],
+			..Default::default()
+		};
+		// sheets of the first file to choose from, if it's a workbook with several
+		let mut sheets = Vec::new();
+		for (i, filename) in filenames.into_iter().enumerate() {
+			if i > 0 {
+				s.buffers.push(Buffer::default());
+				s.switch_buffer(i);
+			}
+			let found = s.open(filename, options.clone())?;
+			if i == 0 {
+				sheets = found;
+			}
+		}
+		s.switch_buffer(0);
+		if sheets.len() > 1 {
+			s.buf.opening = false;
+			s.pick_sheet(sheets);
+		} else {
+			s.finish_open();
+		}
+		Ok(s)
+	}
+
+	/// Merge the key bindings of the config file over the defaults, if there is one.
+	pub fn load_config(&mut self) {
+		let Some(path) = config::path() else {
+			return;
+		};
+		match Config::load(&path) {
+			Ok(config) => self.apply_config(config),
+			Err(e) => {
+				warn!("Error loading config {path:?}: {e}");
+				self.set_status(Status::Config(path, Err(e)));
+			}
+		}
+		let Some(path) = config::macros_path() else {
+			return;
+		};
+		match config::load_macros(&path) {
+			Ok(macros) => self.macros = macros,
+			Err(e) => {
+				warn!("Error loading macros {path:?}: {e}");
+				self.set_status(Status::Config(path, Err(e)));
+			}
+		}
+	}
+
+	fn apply_config(&mut self, config: Config) {
+		self.conflicts = config.apply(&mut self.bindings);
+		self.chord_timeout = config.chord_timeout.map(Duration::from_millis);
+		self.config = config;
+		if self.config.vim || self.vim.is_some() {
+			self.vim = Some(Vim::new(&self.config));
+		}
+	}
+
+	/// Read a file into the current buffer, returning the sheets to choose from if it's a
+	/// workbook with several and none was given.
+	fn open(&mut self, filename: PathBuf, options: FileOptions) -> io::Result<Vec<String>> {
+		let stdio = if filename.as_os_str() == STDIO_PATH {
+			let mut buf = Vec::new();
+			io::stdin().lock().read_to_end(&mut buf)?;
+			Some(buf)
+		} else {
+			None
+		};
+
+		let table = match filename.to_str().and_then(sql::database_path) {
+			Some((db, name)) => Some(sql::Table::open(&db, name)?),
+			None => None,
+		};
+		let mut sheets = Vec::new();
+		let workbook = match filename.to_str().and_then(spreadsheet::workbook_path) {
+			Some((path, sheet)) => {
+				let (workbook, names) = Workbook::open(&path, sheet)?;
+				if sheet.is_none() {
+					sheets = names;
+				}
+				Some(workbook)
+			}
+			None => None,
+		};
+		let format = match (options.format, &options.columns) {
+			(Some(format), _) => format,
+			(None, Some(_)) => Format::Fixed,
+			(None, None) => Format::from_path(&filename),
+		};
+		self.buf = Buffer {
 			filename,
+			options,
+			format,
+			table,
+			workbook,
+			stdio,
+			opening: true,
 			..Default::default()
 		};
-		s.read()?;
+		if let Some(warning) = self.read()? {
+			self.set_status(warning);
+		}
 		// first read shouldn't be undone
-		s.change_tracker = Default::default();
+		self.buf.change_tracker = Default::default();
+		self.watch();
+		if self.buf.options.follow {
+			match self.check_follow() {
+				Ok(()) => self.buf.follow = true,
+				Err(e) => self.set_status(Status::Follow(Err(e))),
+			}
+		}
+		Ok(sheets)
+	}
 
-		Ok(s)
+	/// Show the buffer at `index` in `buffers`.
+	fn switch_buffer(&mut self, index: usize) {
+		mem::swap(&mut self.buf, &mut self.buffers[self.active]);
+		self.active = index;
+		mem::swap(&mut self.buf, &mut self.buffers[self.active]);
+	}
+
+	/// Run `f` with each buffer shown in turn, then show the current buffer again.
+	fn for_each_buffer(&mut self, mut f: impl FnMut(&mut Self)) {
+		let active = self.active;
+		for i in 0..self.buffers.len() {
+			self.switch_buffer(i);
+			f(self);
+		}
+		self.switch_buffer(active);
+	}
+
+	/// Show the buffer at `index` in the focused pane.
+	fn show_buffer(&mut self, index: usize) {
+		self.switch_buffer(index);
+		self.panes[self.focus].buffer = index;
+	}
+
+	/// Show the next or previous buffer, wrapping around.
+	fn cycle_buffer(&mut self, forward: bool) {
+		let n = self.buffers.len();
+		let next = if forward {
+			(self.active + 1) % n
+		} else {
+			(self.active + n - 1) % n
+		};
+		self.show_buffer(next);
+	}
+
+	/// Split the focused pane in two, showing the same buffer at the same position in both.
+	fn split_pane(&mut self, stacked: bool) {
+		let new = self.panes.len();
+		self.panes.push(Pane {
+			buffer: self.active,
+			selection: self.buf.selection,
+			anchor: self.buf.anchor,
+			grid_state: self.buf.grid_state.clone(),
+		});
+		self.split.split(self.focus, new, stacked);
+		self.focus_pane(new);
+	}
+
+	/// Close the focused pane, unless it's the only one.
+	fn close_pane(&mut self) {
+		let panes = self.split.panes();
+		let Some(i) = panes.iter().position(|&p| p == self.focus) else {
+			return;
+		};
+		if !self.split.remove(self.focus) {
+			return;
+		}
+		self.panes.remove(self.focus);
+		// focus the pane before it, or the one after it that took its place
+		let next = panes[i.saturating_sub(1)];
+		self.focus = if next > self.focus { next - 1 } else { next };
+		self.load_pane();
+	}
+
+	/// Focus the next or previous pane, wrapping around.
+	fn cycle_pane(&mut self, forward: bool) {
+		let panes = self.split.panes();
+		let n = panes.len();
+		let i = panes
+			.iter()
+			.position(|&p| p == self.focus)
+			.unwrap_or_default();
+		let next = if forward {
+			(i + 1) % n
+		} else {
+			(i + n - 1) % n
+		};
+		self.focus_pane(panes[next]);
+	}
+
+	fn focus_pane(&mut self, index: usize) {
+		let pane = &mut self.panes[self.focus];
+		pane.selection = self.buf.selection;
+		pane.anchor = self.buf.anchor;
+		pane.grid_state = self.buf.grid_state.clone();
+		self.focus = index;
+		self.load_pane();
+	}
+
+	/// Show the buffer of the focused pane at its position.
+	fn load_pane(&mut self) {
+		self.switch_buffer(self.panes[self.focus].buffer);
+		let pane = &self.panes[self.focus];
+		self.buf.selection = pane.selection;
+		self.buf.anchor = pane.anchor;
+		self.buf.grid_state = pane.grid_state.clone();
+	}
+
+	/// Move the other panes as far as the focused pane moved from `old`.
+	fn scroll_others(&mut self, old: XY<usize>) {
+		let new = self.buf.selection;
+		let dx = new.x as isize - old.x as isize;
+		let dy = new.y as isize - old.y as isize;
+		for (i, pane) in self.panes.iter_mut().enumerate() {
+			if i != self.focus {
+				pane.selection.x = pane.selection.x.saturating_add_signed(dx);
+				pane.selection.y = pane.selection.y.saturating_add_signed(dy);
+			}
+		}
+	}
+
+	/// Ask which buffer to show.
+	fn list_buffers(&mut self) {
+		const KEYS: &str = "123456789abcdefghijklmnopqrstuvwxyz";
+		let mut prompt = PromptState::new("Buffers");
+		let choices: Vec<_> = self
+			.iter_buffers()
+			.map(|b| match b.change_tracker.is_modified() {
+				true => format!("{} (modified)", b.name()),
+				false => b.name(),
+			})
+			.collect();
+		if choices.len() > KEYS.len() {
+			prompt.set_body([format!(
+				"Showing the first {} of {} buffers",
+				KEYS.len(),
+				choices.len()
+			)]);
+		}
+		for (i, (key, desc)) in KEYS.chars().zip(choices).enumerate() {
+			prompt = prompt.choice(key, desc, i);
+		}
+		self.view = ViewState::Buffers(prompt);
+	}
+
+	/// Start recording a macro, once its register is named, or stop and save it.
+	fn toggle_recording(&mut self) {
+		let Some(Recording { register, keys }) = self.recording.take() else {
+			self.macro_step = Some(MacroStep::Record);
+			self.set_status(Status::PickRegister);
+			return;
+		};
+		self.macros.insert(register, keys.into_iter().collect());
+		let result = config::macros_path()
+			.ok_or_else(|| io::Error::other("No config directory"))
+			.and_then(|path| config::save_macros(&path, &self.macros));
+		self.set_status(Status::Recorded(register, result));
+	}
+
+	/// Record or play the macro in the register named by `i`, unless it's not a letter or digit.
+	fn use_register(&mut self, step: MacroStep, i: Input) -> io::Result<Option<ExternalAction>> {
+		let Input(KeyCode::Char(register), KeyModifiers::NONE) = i else {
+			self.clear_status();
+			return Ok(None);
+		};
+		if !register.is_ascii_alphanumeric() {
+			self.clear_status();
+			return Ok(None);
+		}
+		match step {
+			MacroStep::Record => {
+				self.recording = Some(Recording {
+					register,
+					keys: Vec::new(),
+				});
+				self.set_status(Status::Recording(register));
+				Ok(None)
+			}
+			MacroStep::Play(count) => self.play_macro(register, count),
+		}
+	}
+
+	/// Type the keys of a macro `count` times, undoing any changes to each buffer at once.
+	///
+	/// Macros can play macros, but only so deep, as they could play themselves forever.
+	fn play_macro(&mut self, register: char, count: usize) -> io::Result<Option<ExternalAction>> {
+		let Some(keys) = self.macros.get(&register).cloned() else {
+			self.set_status(Status::NoMacro(register));
+			return Ok(None);
+		};
+		if self.playing >= MAX_MACRO_DEPTH {
+			warn!("Not playing macro @{register}, {MAX_MACRO_DEPTH} are already playing");
+			return Ok(None);
+		}
+		self.clear_status();
+		self.playing += 1;
+		let before: Vec<_> = self
+			.iter_buffers()
+			.map(|b| (b.change_tracker.undo_len(), b.change_tracker.version()))
+			.collect();
+		let mut result = Ok(None);
+		'play: for _ in 0..count {
+			for &i in &keys {
+				result = self.handle_input(i);
+				if !matches!(result, Ok(None)) {
+					break 'play;
+				}
+			}
+		}
+		self.playing -= 1;
+		// the macro may have switched buffers, so combine its changes to each one
+		let mut before = before.into_iter();
+		self.for_each_buffer(|s| {
+			let tracker = &mut s.buf.change_tracker;
+			match before.next() {
+				Some((len, version)) if version != tracker.version() => tracker.squash(len),
+				_ => {}
+			}
+		});
+		result
+	}
+
+	/// Set the current cell, first adding any rows and columns needed to reach it, as a single
+	/// change.
+	fn edit_cell(&mut self, contents: String) {
+		self.last_change = Some(LastChange::Edit(contents.clone()));
+		let XY { x, y } = self.buf.selection;
+		let size = self.buf.grid.size();
+		let len = self.buf.change_tracker.undo_len();
+		for col in size.x..=x {
+			self.buf
+				.grid
+				.insert_col(col, Vec::new())
+				.track(&mut self.buf.change_tracker);
+		}
+		for row in size.y..=y {
+			self.buf
+				.grid
+				.insert_row(row, Vec::new())
+				.track(&mut self.buf.change_tracker);
+		}
+		self.buf
+			.grid
+			.edit(self.buf.selection, contents)
+			.track(&mut self.buf.change_tracker);
+		self.buf.change_tracker.squash(len);
+	}
+
+	/// Copy the selected cells, or `count` rows from the current one, to the register.
+	fn yank(&mut self, count: usize) {
+		self.register = Some(match self.selected_range() {
+			Some(range) => {
+				self.buf.anchor = None;
+				Register::Cells(self.copy(range))
+			}
+			None => Register::Rows(self.copy(self.rows_from_cursor(count))),
+		});
+	}
+
+	/// Up to `count` whole rows, starting from the current one
+	fn rows_from_cursor(&self, count: usize) -> MyRect<usize> {
+		let size = self.buf.grid.size();
+		let y = self.buf.selection.y;
+		MyRect {
+			x: 0,
+			y,
+			width: size.x,
+			height: count.min(size.y.saturating_sub(y)),
+		}
+	}
+
+	fn copy(&mut self, range: MyRect<usize>) -> Vec<Vec<String>> {
+		self.buf.grid.load(range.y..range.y + range.height);
+		(range.y..range.y + range.height)
+			.map(|y| {
+				(range.x..range.x + range.width)
+					.map(|x| self.buf.grid.get(XY { x, y }).cloned().unwrap_or_default())
+					.collect()
+			})
+			.collect()
+	}
+
+	/// Insert copied rows after or before the current row, or write copied cells over the
+	/// cells at the cursor, as a single change.
+	fn paste(&mut self, below: bool) {
+		let Some(register) = self.register.clone() else {
+			return;
+		};
+		let size = self.buf.grid.size();
+		let XY { x, y } = self.buf.selection;
+		let len = self.buf.change_tracker.undo_len();
+		match register {
+			Register::Rows(rows) => {
+				let start = if below { (y + 1).min(size.y) } else { y };
+				for (i, mut row) in rows.into_iter().enumerate() {
+					row.truncate(size.x);
+					self.buf
+						.grid
+						.insert_row(start + i, row)
+						.track(&mut self.buf.change_tracker);
+				}
+				self.buf.selection.y = start;
+			}
+			Register::Cells(rows) => {
+				self.buf.grid.load(y..y + rows.len());
+				for (dy, row) in rows.into_iter().enumerate().take(size.y.saturating_sub(y)) {
+					for (dx, cell) in row.into_iter().enumerate().take(size.x.saturating_sub(x)) {
+						let pos = XY {
+							x: x + dx,
+							y: y + dy,
+						};
+						self.buf
+							.grid
+							.edit(pos, cell)
+							.track(&mut self.buf.change_tracker);
+					}
+				}
+			}
+		}
+		self.buf.change_tracker.squash(len);
+	}
+
+	/// Show the key bindings from the config that replaced others.
+	fn list_conflicts(&mut self) {
+		let conflicts = match &self.vim {
+			Some(vim) => &vim.conflicts,
+			None => &self.conflicts,
+		};
+		let body = if conflicts.is_empty() {
+			vec!["No key bindings from the config replace others".to_string()]
+		} else {
+			conflicts.iter().map(ToString::to_string).collect()
+		};
+		self.view = ViewState::Conflicts(
+			PromptState::new("Replaced key bindings")
+				.body(body)
+				.choice('c', "Close", ()),
+		);
+	}
+
+	/// Names of the open buffers, with the current one in brackets and modified ones marked
+	fn buffer_list(&self) -> String {
+		let names: Vec<_> = self
+			.iter_buffers()
+			.enumerate()
+			.map(|(i, b)| {
+				let modified = if b.change_tracker.is_modified() {
+					"+"
+				} else {
+					""
+				};
+				if i == self.active {
+					format!("[{}:{}{modified}]", i + 1, b.name())
+				} else {
+					format!("{}:{}{modified}", i + 1, b.name())
+				}
+			})
+			.collect();
+		names.join(" ")
+	}
+
+	/// Every open buffer, in order
+	fn iter_buffers(&self) -> impl Iterator<Item = &Buffer> {
+		(0..self.buffers.len()).map(|i| {
+			if i == self.active {
+				&self.buf
+			} else {
+				&self.buffers[i]
+			}
+		})
+	}
+
+	/// Latest written contents to emit on stdout, if editing from a pipeline
+	pub fn stdout(&self) -> Option<&[u8]> {
+		self.iter_buffers().find_map(|b| b.stdio.as_deref())
+	}
+
+	fn swap_path(&self) -> Option<PathBuf> {
+		if self.buf.stdio.is_some() {
+			return None;
+		}
+		Some(swap::path_for(&self.buf.filename))
+	}
+
+	/// Offer to recover changes once the file has been loaded, if it's being shown.
+	fn finish_open(&mut self) {
+		if self.buf.opening && !self.is_busy() && matches!(self.view, ViewState::Normal) {
+			self.buf.opening = false;
+			self.check_swap();
+		}
+	}
+
+	/// Offer to recover changes from a swap file left by a previous session.
+	fn check_swap(&mut self) {
+		let Some(path) = self.swap_path() else {
+			return;
+		};
+		if !path.exists() {
+			return;
+		}
+		let swap = match Swap::read(&path) {
+			Ok(swap) => swap,
+			Err(e) => {
+				self.set_status(Status::Swap(path, Err(e)));
+				return;
+			}
+		};
+		info!("Found swap file {path:?} with {} changes", swap.len());
+		let mut body = vec![format!(
+			"Found swap file {path:?} with {} unsaved changes to {:?}.",
+			swap.len(),
+			self.buf.filename
+		)];
+		if !swap.is_stale() {
+			body.push("The program that wrote it may still be running!".to_string());
+		}
+		if swap.is_outdated(&self.buf.filename) {
+			body.push(
+				"The file has changed since, so the changes may not apply to it!".to_string(),
+			);
+		}
+		let prompt = PromptState::new("Recover changes?")
+			.body(body)
+			.choice('r', "Recover", Recovery::Recover)
+			.choice('d', "Discard", Recovery::Discard)
+			.choice('f', "Diff", Recovery::Diff);
+		self.view = ViewState::Recover { prompt, swap };
+	}
+
+	fn handle_recovery(&mut self, choice: Recovery, swap: Swap) {
+		match choice {
+			Recovery::Recover => {
+				let (n, len) = (swap.len(), self.buf.change_tracker.undo_len());
+				let result = swap.apply(&mut self.buf.grid, &mut self.buf.change_tracker);
+				for undo in self.buf.change_tracker.undos_since(len) {
+					shift_widths(&mut self.buf.widths, undo);
+				}
+				self.set_status(Status::Recover(n, result));
+			}
+			Recovery::Discard => self.remove_swap(),
+			Recovery::Diff => {
+				let mut prompt = PromptState::new("Unsaved changes")
+					.choice('r', "Recover", Recovery::Recover)
+					.choice('d', "Discard", Recovery::Discard);
+				match swap.diff(&self.buf.grid) {
+					Ok(diff) => prompt.set_body(diff),
+					Err(e) => prompt.set_body([format!("The changes can't be shown: {e}")]),
+				}
+				self.view = ViewState::Recover { prompt, swap };
+			}
+		}
+	}
+
+	/// Ask which sheet of the workbook to view.
+	fn pick_sheet(&mut self, sheets: Vec<String>) {
+		const KEYS: &str = "123456789abcdefghijklmnopqrstuvwxyz";
+		let mut prompt = PromptState::new("Choose a sheet");
+		if sheets.len() > KEYS.len() {
+			prompt.set_body([format!(
+				"Showing the first {} of {} sheets, open others with {}:SHEET",
+				KEYS.len(),
+				sheets.len(),
+				self.buf.filename.display()
+			)]);
+		}
+		for (key, sheet) in KEYS.chars().zip(sheets) {
+			prompt = prompt.choice(key, sheet.clone(), sheet);
+		}
+		self.view = ViewState::PickSheet(prompt);
+	}
+
+	fn open_sheet(&mut self, sheet: String) {
+		let Some(workbook) = &mut self.buf.workbook else {
+			return;
+		};
+		workbook.set_sheet(sheet);
+		match self.read() {
+			Ok(warning) => {
+				self.buf.change_tracker = Default::default();
+				let status = Status::Read(self.buf.filename.to_owned(), Ok(()));
+				self.set_status(warning.unwrap_or(status));
+			}
+			Err(e) => self.set_status(Status::Read(self.buf.filename.to_owned(), Err(e))),
+		}
+	}
+
+	/// Write to a different file from now on, in the format suggested by its name.
+	fn save_as(&mut self, filename: PathBuf) {
+		self.remove_swap();
+		self.buf.format = self
+			.buf
+			.options
+			.format
+			.unwrap_or_else(|| Format::from_path(&filename));
+		self.buf.compression = Compression::from_extension(&filename);
+		self.buf.encoding = self.buf.options.encoding.unwrap_or_default();
+		self.buf.layout = None;
+		self.buf.types = Default::default();
+		self.buf.workbook = None;
+		self.buf.filename = filename;
+		// overwriting it was asked for
+		self.update_stamp();
+		self.buf.disk_changed = false;
+		self.watch();
+	}
+
+	/// Refuse to write over a workbook being viewed, which would keep only one of its sheets.
+	fn check_not_workbook(&self, path: &Path) -> io::Result<()> {
+		let Ok(path) = fs::canonicalize(path) else {
+			return Ok(());
+		};
+		let is_workbook = self
+			.iter_buffers()
+			.filter_map(|buf| buf.workbook.as_ref())
+			.any(|workbook| fs::canonicalize(workbook.path()).is_ok_and(|p| p == path));
+		if is_workbook {
+			return Err(io::Error::new(
+				io::ErrorKind::AlreadyExists,
+				"Can't write over a workbook that's open",
+			));
+		}
+		Ok(())
+	}
+
+	/// Start watching the file for changes by other programs, if it's a file.
+	fn watch(&mut self) {
+		self.buf.watcher = None;
+		if self.buf.stdio.is_some() || self.buf.table.is_some() || self.buf.workbook.is_some() {
+			return;
+		}
+		match Watcher::new(&self.buf.filename) {
+			Ok(watcher) => self.buf.watcher = Some(watcher),
+			Err(e) => warn!("Can't watch {:?} for changes: {e}", self.buf.filename),
+		}
+	}
+
+	/// Remember the file as it is now, so only changes by other programs are noticed.
+	fn update_stamp(&mut self) {
+		self.buf.stamp = Stamp::of(&self.buf.filename).ok();
+	}
+
+	/// Whether another program changed the file since it was last read or written.
+	fn is_changed_on_disk(&self) -> bool {
+		self.buf.watcher.is_some()
+			&& Stamp::of(&self.buf.filename).is_ok_and(|stamp| self.buf.stamp != Some(stamp))
+	}
+
+	/// Deal with any changes to the file by other programs, adding rows appended to a followed
+	/// file, and asking what to do about other changes once the buffer is `shown`.
+	fn check_disk(&mut self, shown: bool) {
+		if let Some(watcher) = &self.buf.watcher {
+			self.buf.disk_changed |= watcher.changed();
+		}
+		if !self.buf.disk_changed || self.is_busy() || !matches!(self.view, ViewState::Normal) {
+			return;
+		}
+		let Ok(stamp) = Stamp::of(&self.buf.filename) else {
+			// removed, possibly to be replaced
+			self.buf.disk_changed = false;
+			return;
+		};
+		if self.buf.stamp == Some(stamp) {
+			self.buf.disk_changed = false;
+			return;
+		}
+		if let Some(old) = self
+			.buf
+			.stamp
+			.filter(|old| self.buf.follow && stamp.len > old.len)
+		{
+			let result = self.append_rows(old.len);
+			let added = *result.as_ref().unwrap_or(&0);
+			if added > 0 || result.is_err() {
+				self.set_status(Status::Appended(added, result.map(|_| ())));
+			}
+			self.buf.disk_changed = false;
+			self.should_redraw = true;
+			return;
+		}
+		if !shown {
+			// ask once it's shown
+			return;
+		}
+		self.buf.disk_changed = false;
+		info!("{:?} changed on disk", self.buf.filename);
+
+		let mut body = vec![format!(
+			"{:?} was changed by another program.",
+			self.buf.filename
+		)];
+		let mut prompt = PromptState::new("File changed");
+		if self.buf.change_tracker.is_modified() {
+			body.push("Reloading it will discard your unsaved changes.".to_string());
+			prompt = prompt
+				.choice('r', "Reload", DiskChange::Reload)
+				.choice('k', "Keep mine", DiskChange::Keep)
+				.choice('s', "Save mine as", DiskChange::SaveAs);
+		} else {
+			prompt = prompt.choice('r', "Reload", DiskChange::Reload).choice(
+				'i',
+				"Ignore",
+				DiskChange::Keep,
+			);
+		}
+		self.view = ViewState::Changed(prompt.body(body));
+		self.should_redraw = true;
+	}
+
+	fn handle_disk_change(&mut self, choice: DiskChange) -> io::Result<Option<ExternalAction>> {
+		match choice {
+			DiskChange::Reload => return self.handle_action(Action::Read, None),
+			DiskChange::Keep => self.update_stamp(),
+			DiskChange::SaveAs => {
+				self.update_stamp();
+				let stem = self
+					.buf
+					.filename
+					.file_stem()
+					.unwrap_or_default()
+					.to_string_lossy();
+				let name = match self.buf.filename.extension() {
+					Some(ext) => format!("{stem}.mine.{}", ext.to_string_lossy()),
+					None => format!("{stem}.mine"),
+				};
+				self.view = ViewState::SaveAs(InputState::new(
+					"Save your changes to",
+					&self.buf.filename.with_file_name(name).to_string_lossy(),
+				));
+			}
+		}
+		Ok(None)
+	}
+
+	/// Add rows appended to the file after `offset`, returning how many were added.
+	fn append_rows(&mut self, offset: u64) -> io::Result<usize> {
+		let mut file = File::open(&self.buf.filename)?;
+		file.seek(SeekFrom::Start(offset))?;
+		let mut bytes = Vec::new();
+		file.read_to_end(&mut bytes)?;
+		// leave any partial line to be read once it's finished
+		let end = bytes.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+		let mut encoding = self.buf.encoding;
+		let text = encoding.decode(&bytes[..end]).text;
+		let rows = Format::Csv.read(&text)?.to_vec()?;
+		let added = rows.len();
+
+		let at_end = self.buf.selection.y + 1 >= self.buf.grid.size().y;
+		self.buf.grid.append_rows(rows);
+		if at_end {
+			self.buf.selection.y = self.buf.grid.size().y.saturating_sub(1);
+		}
+		self.buf.stamp = Some(Stamp {
+			len: offset + end as u64,
+			..Stamp::of(&self.buf.filename)?
+		});
+		Ok(added)
+	}
+
+	/// Check that rows appended to the file can be read as they're added.
+	fn check_follow(&self) -> io::Result<()> {
+		if self.buf.watcher.is_none()
+			|| self.buf.format != Format::Csv
+			|| self.buf.compression != Compression::None
+		{
+			return Err(io::Error::other(
+				"only uncompressed CSV files can be followed",
+			));
+		}
+		Ok(())
+	}
+
+	fn toggle_follow(&mut self) {
+		if !self.buf.follow {
+			if let Err(e) = self.check_follow() {
+				self.set_status(Status::Follow(Err(e)));
+				return;
+			}
+		}
+		self.buf.follow = !self.buf.follow;
+		self.set_status(Status::Follow(Ok(self.buf.follow)));
+	}
+
+	/// Perform background work between inputs.
+	pub fn tick(&mut self) {
+		self.for_each_buffer(|s| {
+			s.refresh();
+			s.poll_job();
+			s.autosave();
+		});
+		let active = self.active;
+		self.for_each_buffer(|s| s.check_disk(s.active == active));
+		self.finish_open();
+		if let (Some(timeout), Some(last)) = (self.chord_timeout, self.last_input) {
+			if self.input_buf.is_pending() && last.elapsed() >= timeout {
+				debug!("Chord {} timed out", self.input_buf);
+				self.input_buf.clear();
+				self.should_redraw = true;
+			}
+		}
+		if self.is_busy() {
+			// update progress
+			self.should_redraw = true;
+		}
+	}
+
+	/// How long to wait for input before the next `tick`
+	pub fn tick_interval(&self) -> Duration {
+		let interval = self.background_interval();
+		match (self.chord_timeout, self.last_input) {
+			(Some(timeout), Some(last)) if self.input_buf.is_pending() => {
+				interval.min(timeout.saturating_sub(last.elapsed()))
+			}
+			_ => interval,
+		}
+	}
+
+	fn background_interval(&self) -> Duration {
+		if self.iter_buffers().any(Buffer::is_busy) {
+			PROGRESS_INTERVAL
+		} else if self.iter_buffers().any(|b| b.watcher.is_some()) {
+			WATCH_INTERVAL
+		} else {
+			swap::INTERVAL
+		}
+	}
+
+	/// Whether the file is being read or written in the background
+	fn is_busy(&self) -> bool {
+		self.buf.is_busy()
+	}
+
+	/// Stop reading or writing the file in the background.
+	fn cancel(&mut self) {
+		if let Some(job) = &self.buf.job {
+			job.cancel();
+		} else if self.buf.indexing {
+			self.buf.grid.cancel();
+		}
+	}
+
+	/// Description of the read or write running in the background, if any
+	fn progress_msg(&self) -> Option<String> {
+		let (verb, progress) = match &self.buf.job {
+			Some(Job::Read(task)) => ("Reading", task.progress()),
+			Some(Job::Write { task, .. }) => ("Writing", task.progress()),
+			None if self.buf.indexing => {
+				let progress = self.buf.grid.progress()?;
+				let bytes = task::Progress {
+					unit: Unit::Bytes,
+					done: progress.bytes,
+					total: progress.total,
+				};
+				return Some(format!(
+					"Loading {} rows, {bytes}, Esc to cancel",
+					progress.rows
+				));
+			}
+			None => return None,
+		};
+		Some(format!("{verb} {progress}, Esc to cancel"))
+	}
+
+	/// Finish a read or write running in the background, if it's done.
+	fn poll_job(&mut self) {
+		let Some(job) = self.buf.job.take() else {
+			return;
+		};
+		match job {
+			Job::Read(task) => match task.poll() {
+				None => self.buf.job = Some(Job::Read(task)),
+				Some(Ok(loaded)) => {
+					let warning = self.finish_read(loaded);
+					let startup_error = self.status_msg.as_ref().is_some_and(Status::is_err);
+					if self.buf.opening {
+						// first read shouldn't be undone
+						self.buf.change_tracker = Default::default();
+					}
+					self.buf.change_tracker.mark_saved();
+					let status = Status::Read(self.buf.filename.to_owned(), Ok(()));
+					// keep showing errors from starting up, like a bad config
+					if warning.is_some() || !(self.buf.opening && startup_error) {
+						self.set_status(warning.unwrap_or(status));
+					}
+				}
+				Some(Err(e)) => self.set_status(Status::Read(self.buf.filename.to_owned(), Err(e))),
+			},
+			Job::Write { task, version } => match task.poll() {
+				None => self.buf.job = Some(Job::Write { task, version }),
+				Some(Ok(())) => {
+					self.update_stamp();
+					if self.buf.change_tracker.version() == version {
+						self.buf.change_tracker.mark_saved();
+						self.remove_swap();
+					} else {
+						// changed while writing
+						self.buf.change_tracker.forget_saved();
+					}
+					self.set_status(Status::Write(self.buf.filename.to_owned(), Ok(())));
+				}
+				Some(Err(e)) => {
+					self.set_status(Status::Write(self.buf.filename.to_owned(), Err(e)))
+				}
+			},
+		}
+		self.should_redraw = true;
+	}
+
+	/// Add any rows indexed in the background to the grid.
+	fn refresh(&mut self) {
+		if !self.buf.indexing {
+			return;
+		}
+		if self.buf.grid.refresh() {
+			self.should_redraw = true;
+		}
+		let Some(progress) = self.buf.grid.progress() else {
+			self.buf.indexing = false;
+			return;
+		};
+		if !progress.done {
+			return;
+		}
+		self.buf.indexing = false;
+		let path = self.buf.filename.to_owned();
+		let status = match progress.error {
+			Some(e) => Status::Read(path, Err(io::Error::new(io::ErrorKind::InvalidData, e))),
+			None => match progress.first_invalid_line {
+				Some(first_line) => Status::Decode {
+					encoding: self.buf.encoding.name().to_string(),
+					errors: progress.invalid,
+					first_line,
+				},
+				None => Status::Read(path, Ok(())),
+			},
+		};
+		self.set_status(status);
+		self.should_redraw = true;
+	}
+
+	/// Keep the swap file up to date with any unsaved changes.
+	fn autosave(&mut self) {
+		if matches!(self.view, ViewState::Recover { .. }) {
+			// don't overwrite a swap file that hasn't been dealt with yet
+			return;
+		}
+		let version = self.buf.change_tracker.version();
+		if version == self.buf.swap_version {
+			return;
+		}
+		if self
+			.buf
+			.last_swap
+			.is_some_and(|t| t.elapsed() < swap::INTERVAL)
+		{
+			return;
+		}
+		let Some(path) = self.swap_path() else {
+			return;
+		};
+		let result = if self.buf.change_tracker.is_modified() {
+			Swap::new(&self.buf.change_tracker, &self.buf.grid, self.buf.stamp)
+				.and_then(|swap| swap.write(&path))
+		} else {
+			swap::remove(&path)
+		};
+		match result {
+			Ok(()) => debug!("{}", Status::Swap(path, Ok(()))),
+			Err(e) => {
+				self.set_status(Status::Swap(path, Err(e)));
+				self.should_redraw = true;
+			}
+		}
+		self.buf.swap_version = version;
+		self.buf.last_swap = Some(Instant::now());
+	}
+
+	fn remove_swap(&mut self) {
+		self.buf.swap_version = self.buf.change_tracker.version();
+		let Some(path) = self.swap_path() else {
+			return;
+		};
+		if let Err(e) = swap::remove(&path) {
+			self.set_status(Status::Swap(path, Err(e)));
+		}
+	}
+
+	/// Cells between the anchor and the cursor, if selecting a range
+	fn selected_range(&self) -> Option<MyRect<usize>> {
+		range_between(self.buf.selection, self.buf.anchor?)
+	}
+
+	/// The selected range, or the entire grid
+	fn excerpt_range(&self) -> MyRect<usize> {
+		let size = self.buf.grid.size();
+		self.selected_range().unwrap_or(MyRect {
+			x: 0,
+			y: 0,
+			width: size.x,
+			height: size.y,
+		})
+	}
+
+	/// Read the header and rows of the excerpt that aren't in memory.
+	fn load_excerpt(&mut self) {
+		let range = self.excerpt_range();
+		self.buf.grid.load(0..1);
+		self.buf.grid.load(range.y..range.y + range.height);
+	}
+
+	/// The selected range, or the entire grid, with the first row of the grid as the header.
+	fn excerpt(&self) -> Excerpt<'_> {
+		let range = self.excerpt_range();
+		let row = |y| {
+			(range.x..range.x + range.width)
+				.map(|x| self.buf.grid.get(XY { x, y }).map_or("", String::as_str))
+				.collect::<Vec<_>>()
+		};
+		let name = match (&self.buf.stdio, &self.buf.workbook) {
+			(Some(_), _) => "stdin",
+			(None, Some(workbook)) => workbook.sheet(),
+			(None, None) => self
+				.buf
+				.filename
+				.file_name()
+				.and_then(|n| n.to_str())
+				.and_then(|n| n.split('.').next())
+				.unwrap_or("sheet"),
+		};
+		Excerpt {
+			name,
+			header: row(0),
+			rows: (range.y.max(1)..range.y + range.height).map(row).collect(),
+		}
+	}
+
+	/// Write the excerpt to a file, or into a table if `path` is a SQLite database.
+	fn export(&mut self, format: TableFormat, path: &str) -> io::Result<()> {
+		self.check_not_workbook(Path::new(path))?;
+		self.load_excerpt();
+		let mut excerpt = self.excerpt();
+		if format == TableFormat::Sql {
+			if let Some((db, table)) = sql::database_path(path) {
+				excerpt.name = table.unwrap_or(excerpt.name);
+				let rows = sql::write_database(&db, &excerpt)?;
+				info!("Inserted {rows} rows into {db:?}");
+				return Ok(());
+			}
+		}
+		fs::write(path, format.render(&excerpt))
 	}
 
 	fn handle_move(&mut self, m: Direction) {
 		use Direction::*;
-		let XY { x, y } = self.selection;
+		let XY { x, y } = self.buf.selection;
 		let s = match m {
 			Up if y > 0 => XY { x, y: y - 1 },
 			Down => XY { x, y: y + 1 },
@@ -137,63 +1647,156 @@ impl Program {
 			Right => XY { x: x + 1, y },
 			_ => return,
 		};
-		self.selection = s;
+		self.buf.selection = s;
 	}
 
 	fn handle_jump(&mut self, m: Direction) {
 		use Direction::*;
-		let XY { x, y } = self.selection;
+		let XY { x, y } = self.buf.selection;
 		let XY {
 			x: scroll_x,
 			y: scroll_y,
-		} = self.grid_state.scroll();
+		} = self.buf.grid_state.scroll();
 		let XY {
 			x: width,
 			y: height,
 			..
-		} = self.grid_state.visible_cells();
+		} = self.buf.grid_state.visible_cells();
 		match m {
 			Up => {
-				self.selection.y = y.saturating_sub(height);
-				self.grid_state.scroll_mut().y = scroll_y.saturating_sub(height);
+				self.buf.selection.y = y.saturating_sub(height);
+				self.buf.grid_state.scroll_mut().y = scroll_y.saturating_sub(height);
 			}
 			Down => {
-				self.selection.y = y + height;
-				self.grid_state.scroll_mut().y += height;
+				self.buf.selection.y = y + height;
+				self.buf.grid_state.scroll_mut().y += height;
 			}
 			Left => {
 				// TODO: these don't take into account the width of the next columns.
 				// Better method would be using the width iterator in GridView and filling the next screen...
-				self.selection.x = x.saturating_sub(width);
-				self.grid_state.scroll_mut().x = scroll_x.saturating_sub(width);
+				self.buf.selection.x = x.saturating_sub(width);
+				self.buf.grid_state.scroll_mut().x = scroll_x.saturating_sub(width);
 			}
 			Right => {
-				self.selection.x = x + width;
-				self.grid_state.scroll_mut().x += width;
+				self.buf.selection.x = x + width;
+				self.buf.grid_state.scroll_mut().x += width;
 			}
 		}
 	}
 
 	pub fn handle_input(&mut self, i: Input) -> io::Result<Option<ExternalAction>> {
+		if let (Some(recording), 0) = (&mut self.recording, self.playing) {
+			recording.keys.push(i);
+		}
 		let action = match &mut self.view {
 			ViewState::Normal => self.handle_input_normal(i)?,
 			ViewState::EditCell(state) => {
-				if let ControlFlow::Break(o) = state.handle_input(i) {
-					if let Some(new_contents) = o {
-						self.grid
-							.edit(self.selection, new_contents)
-							.track(&mut self.change_tracker);
+				// Enter and Tab move to the next cell down or right, for typing in a column or row
+				let (flow, next) = match (&self.vim, i) {
+					// leaving insert mode keeps the edit
+					(Some(_), Input(KeyCode::Esc, _)) => {
+						(state.handle_input(EditAction::Submit), None)
 					}
+					(_, Input(KeyCode::Enter, _)) => (
+						state.handle_input(EditAction::Submit),
+						Some(Direction::Down),
+					),
+					(_, Input(KeyCode::Tab, _)) => (
+						state.handle_input(EditAction::Submit),
+						Some(Direction::Right),
+					),
+					_ => (state.handle_input(i), None),
+				};
+				if let ControlFlow::Break(o) = flow {
 					self.view = ViewState::Normal;
+					if let Some(new_contents) = o {
+						self.edit_cell(new_contents);
+						if let Some(d) = next {
+							self.handle_move(d);
+						}
+					}
 				}
 				None
 			}
 			ViewState::Palette(state) => {
 				if let ControlFlow::Break(o) = state.handle_input(i) {
 					self.view = ViewState::Normal;
-					if let Some(action) = o {
+					if let Some((action, argument)) = o {
 						self.should_redraw = true;
-						return self.handle_action(action);
+						return match argument {
+							Some(argument) => self.handle_argument(action, &argument),
+							None => self.handle_action(action, None),
+						};
+					}
+				}
+				None
+			}
+			ViewState::Export { format, input } => {
+				if let ControlFlow::Break(o) = input.handle_input(i) {
+					let format = *format;
+					self.view = ViewState::Normal;
+					if let Some(path) = o.filter(|p| !p.trim().is_empty()) {
+						let result = self.export(format, &path);
+						self.set_status(Status::Export(path.into(), result));
+					}
+				}
+				None
+			}
+			ViewState::SaveAs(input) => {
+				if let ControlFlow::Break(o) = input.handle_input(i) {
+					self.view = ViewState::Normal;
+					if let Some(path) = o.filter(|p| !p.trim().is_empty()) {
+						let path = PathBuf::from(path);
+						if let Err(e) = self.check_not_workbook(&path) {
+							self.set_status(Status::Write(path, Err(e)));
+							return Ok(None);
+						}
+						self.save_as(path);
+						self.should_redraw = true;
+						return self.handle_action(Action::Write, None);
+					}
+				}
+				None
+			}
+			ViewState::PickSheet(prompt) => {
+				if let ControlFlow::Break(o) = prompt.handle_input(i) {
+					self.view = ViewState::Normal;
+					if let Some(sheet) = o {
+						self.open_sheet(sheet);
+					}
+					self.check_swap();
+				}
+				None
+			}
+			ViewState::Conflicts(prompt) => {
+				if let ControlFlow::Break(_) = prompt.handle_input(i) {
+					self.view = ViewState::Normal;
+				}
+				None
+			}
+			ViewState::Buffers(prompt) => {
+				if let ControlFlow::Break(o) = prompt.handle_input(i) {
+					self.view = ViewState::Normal;
+					if let Some(index) = o {
+						self.show_buffer(index);
+					}
+				}
+				None
+			}
+			ViewState::Changed(prompt) => {
+				if let ControlFlow::Break(o) = prompt.handle_input(i) {
+					self.view = ViewState::Normal;
+					self.should_redraw = true;
+					return self.handle_disk_change(o.unwrap_or(DiskChange::Keep));
+				}
+				None
+			}
+			ViewState::Recover { prompt, swap } => {
+				if let ControlFlow::Break(o) = prompt.handle_input(i) {
+					let swap = swap.clone();
+					self.view = ViewState::Normal;
+					if let Some(choice) = o {
+						self.handle_recovery(choice, swap);
 					}
 				}
 				None
@@ -208,9 +1811,87 @@ impl Program {
 		Ok(action)
 	}
 
+	/// Bindings for the current mode
+	fn active_bindings(&self) -> &Bindings<Action> {
+		match &self.vim {
+			Some(vim) => vim.bindings(self.buf.anchor.is_some()),
+			None => &self.bindings,
+		}
+	}
+
+	/// Mode of the vim-style bindings, if they're enabled and apply to the current view
+	fn vim_mode(&self) -> Option<VimMode> {
+		self.vim.as_ref()?;
+		match self.view {
+			ViewState::EditCell(_) => Some(VimMode::Insert),
+			ViewState::Normal if self.buf.anchor.is_some() => Some(VimMode::Visual),
+			ViewState::Normal => Some(VimMode::Normal),
+			_ => None,
+		}
+	}
+
+	fn toggle_vim(&mut self) {
+		self.vim = match self.vim {
+			Some(_) => None,
+			None => Some(Vim::new(&self.config)),
+		};
+		self.input_buf.clear();
+	}
+
+	/// Text to start replacing the current cell with, if `i` is an unbound character or follows
+	/// a count that isn't followed by a binding, which is a number being typed instead.
+	///
+	/// Vim-style bindings have an insert mode for that.
+	fn typed_text(&self, i: Input) -> Option<String> {
+		if self.vim.is_some() || !self.input_buf.is_empty() {
+			return None;
+		}
+		let digits = self.input_buf.digits();
+		match i {
+			Input(KeyCode::Char(c), KeyModifiers::NONE)
+				if self.active_bindings().get(&[i]).is_none() =>
+			{
+				Some(format!("{digits}{c}"))
+			}
+			// commits the number
+			Input(KeyCode::Enter | KeyCode::Tab, KeyModifiers::NONE) if !digits.is_empty() => {
+				Some(digits.to_string())
+			}
+			_ => None,
+		}
+	}
+
 	fn handle_input_normal(&mut self, i: Input) -> io::Result<Option<ExternalAction>> {
+		self.last_input = Some(Instant::now());
+		if let Some(step) = self.macro_step.take() {
+			return self.use_register(step, i);
+		}
+		if let (Input(KeyCode::Esc, KeyModifiers::NONE), true) = (i, self.input_buf.is_pending()) {
+			debug!("Cancelled chord {}", self.input_buf);
+			self.input_buf.clear();
+			return Ok(None);
+		}
+		// digits before a binding are a count of how many times to do it, once started by an
+		// unbound digit other than `0`
+		if let Input(KeyCode::Char(c @ '0'..='9'), KeyModifiers::NONE) = i {
+			let counting = self.input_buf.count().is_some()
+				|| (c != '0' && self.active_bindings().get(&[i]).is_none());
+			if counting && self.input_buf.is_empty() {
+				self.input_buf.push_digit(c);
+				return Ok(None);
+			}
+		}
+		if let Some(text) = self.typed_text(i) {
+			self.input_buf.clear();
+			self.view = ViewState::EditCell(EditState::from_str(&text));
+			self.clear_status();
+			if let Input(KeyCode::Enter | KeyCode::Tab, _) = i {
+				return self.handle_input(i);
+			}
+			return Ok(None);
+		}
 		self.input_buf.push(i);
-		let &action = match self.bindings.get(&self.input_buf) {
+		let &action = match self.active_bindings().get(&self.input_buf) {
 			None => {
 				debug!("Unhandled input: {i}");
 				self.input_buf.clear();
@@ -222,86 +1903,337 @@ impl Program {
 			Some(BindNode::Action(a)) => a,
 		};
 		debug!("{} -> {action:?}", self.input_buf);
+		let count = self.input_buf.count();
+		if let (Action::RecordMacro, Some(recording)) = (action, &mut self.recording) {
+			// the keys that stop recording aren't part of the macro
+			let len = recording.keys.len().saturating_sub(self.input_buf.typed());
+			recording.keys.truncate(len);
+		}
 		self.input_buf.clear();
 
-		self.handle_action(action)
+		let before = (self.focus, self.active, self.buf.selection);
+		let result = self.handle_action(action, count);
+		if self.sync_scroll && (self.focus, self.active) == (before.0, before.1) {
+			self.scroll_others(before.2);
+		}
+		result
+	}
+
+	/// Do `action` as many times as `count` if it repeats, undoing any changes it makes at once.
+	fn handle_action(
+		&mut self,
+		action: Action,
+		count: Option<usize>,
+	) -> io::Result<Option<ExternalAction>> {
+		use Action::*;
+		match action {
+			Repeat => return self.repeat_change(count),
+			PlayMacro => {
+				self.macro_step = Some(MacroStep::Play(count.unwrap_or(1)));
+				self.set_status(Status::PickRegister);
+				return Ok(None);
+			}
+			_ => {}
+		}
+		if action.argument().is_some() {
+			return match count {
+				// the count is the argument, like the row to go to
+				Some(count) => self.handle_argument(action, &count.to_string()),
+				None => {
+					self.view = ViewState::Palette(PaletteState::argument(action));
+					Ok(None)
+				}
+			};
+		}
+		if action.changes() {
+			self.last_change = Some(LastChange::Action(action, count));
+		}
+		let count = count.unwrap_or(1);
+		match action {
+			Yank => self.yank(count),
+			// deleted rows can be pasted back
+			DeleteRow if self.buf.selection.y < self.buf.grid.size().y => {
+				let rows = self.copy(self.rows_from_cursor(count));
+				self.register = Some(Register::Rows(rows));
+			}
+			_ => {}
+		}
+		if !action.repeats() {
+			return self.handle_action_once(action);
+		}
+		let (active, len) = (self.active, self.buf.change_tracker.undo_len());
+		for _ in 0..count {
+			if let Some(external) = self.handle_action_once(action)? {
+				return Ok(Some(external));
+			}
+		}
+		// the length is of the buffer shown before, so leave any other one be
+		if !matches!(action, Undo | Redo) && self.active == active {
+			self.buf.change_tracker.squash(len);
+		}
+		Ok(None)
+	}
+
+	/// Do an action that takes an argument, with what was typed for it.
+	fn handle_argument(
+		&mut self,
+		action: Action,
+		argument: &str,
+	) -> io::Result<Option<ExternalAction>> {
+		use Action::*;
+		let argument = argument.trim();
+		let result = match action {
+			GoTo => self.go_to(argument),
+			InsertRows => match parse_number(argument) {
+				Ok(n) => return self.handle_action(InsertRow, Some(n)),
+				Err(e) => Err(e),
+			},
+			SortAscending | SortDescending => self.sort(argument, action == SortDescending),
+			SetWidth => self.set_width(argument),
+			_ => return self.handle_action(action, None),
+		};
+		if let Err(msg) = result {
+			self.set_status(Status::Argument(msg));
+		}
+		Ok(None)
+	}
+
+	/// Go to the row, or column and row, written like `12` or `3,12`, within the grid.
+	fn go_to(&mut self, argument: &str) -> Result<(), String> {
+		let (x, y) = match argument.split_once(',') {
+			Some((x, y)) => (parse_number(x.trim())? - 1, parse_number(y.trim())? - 1),
+			None => (self.buf.selection.x, parse_number(argument)? - 1),
+		};
+		let size = self.buf.grid.size();
+		self.buf.selection = XY {
+			x: x.min(size.x.saturating_sub(1)),
+			y: y.min(size.y.saturating_sub(1)),
+		};
+		Ok(())
+	}
+
+	/// Sort the selected rows, or every row, by the column numbered `argument`, or else the
+	/// current one.
+	fn sort(&mut self, argument: &str, descending: bool) -> Result<(), String> {
+		let col = match argument {
+			"" => self.buf.selection.x,
+			arg => parse_number(arg)? - 1,
+		};
+		if col >= self.buf.grid.size().x {
+			return Err(format!("There's no column {}", col + 1));
+		}
+		// the header stays on top
+		let header = self.buf.header_rows();
+		let rows = match self.selected_range() {
+			Some(range) => range.y.max(header)..range.y + range.height,
+			None => header..self.buf.grid.size().y,
+		};
+		if rows.is_empty() {
+			return Ok(());
+		}
+		self.buf
+			.grid
+			.sort_rows(rows, col, descending)
+			.track(&mut self.buf.change_tracker);
+		Ok(())
+	}
+
+	/// Set the width of the current column to `argument`, or fit it to its contents if that's
+	/// empty.
+	fn set_width(&mut self, argument: &str) -> Result<(), String> {
+		let col = self.buf.selection.x;
+		if argument.is_empty() {
+			self.buf.widths.remove(&col);
+			return Ok(());
+		}
+		let width = parse_number(argument)?;
+		let width = u16::try_from(width).map_err(|_| format!("{width} is too wide"))?;
+		self.buf.widths.insert(col, width);
+		Ok(())
+	}
+
+	/// Make the last change again at the cursor, with `count` instead of its count if given.
+	fn repeat_change(&mut self, count: Option<usize>) -> io::Result<Option<ExternalAction>> {
+		match self.last_change.clone() {
+			Some(LastChange::Action(action, last)) => self.handle_action(action, count.or(last)),
+			Some(LastChange::Edit(contents)) => {
+				self.edit_cell(contents);
+				Ok(None)
+			}
+			None => Ok(None),
+		}
 	}
 
-	fn handle_action(&mut self, action: Action) -> io::Result<Option<ExternalAction>> {
+	fn handle_action_once(&mut self, action: Action) -> io::Result<Option<ExternalAction>> {
 		use Action::*;
 		match action {
-			Quit => return Ok(Some(ExternalAction::Quit)),
-			Write => self.set_status(Status::Write(self.filename.to_owned(), self.write())),
-			Read => {
-				let result = self.read();
-				self.set_status(Status::Read(self.filename.to_owned(), result));
+			Quit => {
+				self.for_each_buffer(Self::remove_swap);
+				return Ok(Some(ExternalAction::Quit));
+			}
+			Write if self.buf.workbook.is_some() => {
+				let path = self
+					.buf
+					.workbook
+					.as_ref()
+					.map(|w| w.path().with_extension("csv"))
+					.unwrap_or_default();
+				self.view = ViewState::SaveAs(InputState::new(
+					"Workbooks are read-only, save sheet to",
+					&path.to_string_lossy(),
+				));
+				self.clear_status();
+			}
+			Write if !self.is_busy() && self.is_changed_on_disk() => {
+				// ask before overwriting changes by another program
+				self.buf.disk_changed = true;
+				self.check_disk(true);
 			}
+			Write => match self.write() {
+				// reported once it's done
+				Ok(_) if self.buf.job.is_some() => self.clear_status(),
+				Ok(warning) => {
+					self.buf.change_tracker.mark_saved();
+					self.remove_swap();
+					let status = Status::Write(self.buf.filename.to_owned(), Ok(()));
+					self.set_status(warning.unwrap_or(status));
+				}
+				Err(e) => self.set_status(Status::Write(self.buf.filename.to_owned(), Err(e))),
+			},
+			Read => match self.read() {
+				// reported once it's done
+				Ok(_) if self.buf.job.is_some() => self.clear_status(),
+				Ok(warning) => {
+					self.buf.change_tracker.mark_saved();
+					let status = Status::Read(self.buf.filename.to_owned(), Ok(()));
+					self.set_status(warning.unwrap_or(status));
+				}
+				Err(e) => self.set_status(Status::Read(self.buf.filename.to_owned(), Err(e))),
+			},
 			Move(d) => self.handle_move(d),
 			Jump(d) => self.handle_jump(d),
 			Home => {
-				self.selection = XY { x: 0, y: 0 };
+				self.buf.selection = XY { x: 0, y: 0 };
 			}
 			End => {
-				let XY { x, y } = self.grid.size();
+				let XY { x, y } = self.buf.grid.size();
 				let x = x.saturating_sub(1);
 				let y = y.saturating_sub(1);
-				self.selection = XY { x, y };
+				self.buf.selection = XY { x, y };
 			}
 			HomeRow => {
-				self.selection.x = 0;
+				self.buf.selection.x = 0;
 			}
 			EndRow => {
-				self.selection.x = self.grid.size().x.saturating_sub(1);
+				self.buf.selection.x = self.buf.grid.size().x.saturating_sub(1);
 			}
 			HomeCol => {
-				self.selection.y = 0;
+				self.buf.selection.y = 0;
 			}
 			EndCol => {
-				self.selection.y = self.grid.size().y.saturating_sub(1);
-			}
-			GoTo => todo!(), // dialog
-			Edit => {
-				self.view = ViewState::EditCell(EditState::from_str(
-					self.grid
-						.get(self.selection)
-						.expect("TODO: edit cells outside of grid"),
-				));
+				self.buf.selection.y = self.buf.grid.size().y.saturating_sub(1);
+			}
+			Edit | EditFromStart => {
+				self.buf
+					.grid
+					.load(self.buf.selection.y..self.buf.selection.y + 1);
+				// cells past the edge are empty, and the grid grows to reach them once edited
+				let contents = self.buf.grid.get(self.buf.selection);
+				let mut state = EditState::from_str(contents.map_or("", String::as_str));
+				if action == EditFromStart {
+					state.move_beginning();
+				}
+				self.view = ViewState::EditCell(state);
 				self.clear_status();
 			}
 			Replace => {
 				self.view = ViewState::EditCell(EditState::from_str(""));
 				self.clear_status();
 			}
-			Clear => self
-				.grid
-				.edit(self.selection, String::new())
-				.track(&mut self.change_tracker),
+			Clear => match self.selected_range() {
+				Some(range) => {
+					let len = self.buf.change_tracker.undo_len();
+					for y in range.y..range.y + range.height {
+						for x in range.x..range.x + range.width {
+							self.buf
+								.grid
+								.edit(XY { x, y }, String::new())
+								.track(&mut self.buf.change_tracker);
+						}
+					}
+					self.buf.change_tracker.squash(len);
+					self.buf.anchor = None;
+				}
+				None if self.buf.selection.x < self.buf.grid.size().x
+					&& self.buf.selection.y < self.buf.grid.size().y =>
+				{
+					self.buf
+						.grid
+						.edit(self.buf.selection, String::new())
+						.track(&mut self.buf.change_tracker)
+				}
+				// nothing to clear past the end of the grid
+				None => {}
+			},
 			InsertRow => self
+				.buf
 				.grid
-				.insert_row(self.selection.y, Vec::new())
-				.track(&mut self.change_tracker),
-			InsertCol => self
-				.grid
-				.insert_col(self.selection.x, Vec::new())
-				.track(&mut self.change_tracker),
+				.insert_row(self.buf.selection.y, Vec::new())
+				.track(&mut self.buf.change_tracker),
+			InsertCol => {
+				let undo = self.buf.grid.insert_col(self.buf.selection.x, Vec::new());
+				shift_widths(&mut self.buf.widths, &undo);
+				undo.track(&mut self.buf.change_tracker);
+			}
+			// past the last row or column, after deleting it with a count
+			DeleteRow if self.buf.selection.y >= self.buf.grid.size().y => {}
+			DeleteCol if self.buf.selection.x >= self.buf.grid.size().x => {}
 			DeleteRow => self
+				.buf
 				.grid
-				.delete_row(self.selection.y)
-				.track(&mut self.change_tracker),
-			DeleteCol => self
-				.grid
-				.delete_col(self.selection.x)
-				.track(&mut self.change_tracker),
-			Undo => {
-				if let None = self.change_tracker.undo(&mut self.grid) {
-					self.set_status(Status::UndoLimit)
-				}
+				.delete_row(self.buf.selection.y)
+				.track(&mut self.buf.change_tracker),
+			DeleteCol => {
+				let undo = self.buf.grid.delete_col(self.buf.selection.x);
+				shift_widths(&mut self.buf.widths, &undo);
+				undo.track(&mut self.buf.change_tracker);
+			}
+			Select => {
+				self.buf.anchor = match self.buf.anchor {
+					Some(_) => None,
+					None => Some(self.buf.selection),
+				};
 			}
-			Redo => {
-				if let None = self.change_tracker.redo(&mut self.grid) {
-					self.set_status(Status::RedoLimit)
+			// copied by handle_action, for its count
+			Yank => {}
+			PasteBelow => self.paste(true),
+			PasteAbove => self.paste(false),
+			Export(format) => {
+				let path = match self.buf.stdio {
+					Some(_) => PathBuf::from("table"),
+					None => self.buf.filename.clone(),
 				}
+				.with_extension(format.extension());
+				let title = format!("Export {format:?} table to");
+				self.view = ViewState::Export {
+					format,
+					input: InputState::new(title, &path.to_string_lossy()),
+				};
+				self.clear_status();
+			}
+			CopyAs(format) => {
+				self.load_excerpt();
+				let result = clipboard::copy(format.render(&self.excerpt()));
+				self.set_status(Status::Copy(result));
 			}
+			Undo => match self.buf.change_tracker.undo(&mut self.buf.grid) {
+				Some(redo) => shift_widths(&mut self.buf.widths, redo),
+				None => self.set_status(Status::UndoLimit),
+			},
+			Redo => match self.buf.change_tracker.redo(&mut self.buf.grid) {
+				Some(undo) => shift_widths(&mut self.buf.widths, undo),
+				None => self.set_status(Status::RedoLimit),
+			},
 			ToggleDebug => {
 				self.view = match self.view {
 					ViewState::Debug => ViewState::Normal,
@@ -311,9 +2243,25 @@ impl Program {
 			TogglePalette => {
 				self.view = match self.view {
 					ViewState::Palette(_) => ViewState::Normal,
-					_ => ViewState::Palette(PaletteState::new(&self.bindings)),
+					_ => ViewState::Palette(PaletteState::new(self.active_bindings())),
 				};
 			}
+			Cancel => self.cancel(),
+			ToggleFollow => self.toggle_follow(),
+			NextBuffer => self.cycle_buffer(true),
+			PrevBuffer => self.cycle_buffer(false),
+			ListBuffers => self.list_buffers(),
+			SplitHorizontal => self.split_pane(true),
+			SplitVertical => self.split_pane(false),
+			ClosePane => self.close_pane(),
+			NextPane => self.cycle_pane(true),
+			PrevPane => self.cycle_pane(false),
+			ToggleSyncScroll => self.sync_scroll = !self.sync_scroll,
+			ListConflicts => self.list_conflicts(),
+			ToggleVim => self.toggle_vim(),
+			// with their counts or arguments, by handle_action
+			Repeat | PlayMacro | GoTo | InsertRows | SortAscending | SortDescending | SetWidth => {}
+			RecordMacro => self.toggle_recording(),
 			DumpState => self.set_status(Status::DumpState(crate::write_state_to_temp(self))),
 		}
 		Ok(None)
@@ -332,21 +2280,185 @@ impl Program {
 		self.status_msg = None;
 	}
 
-	fn write(&self) -> io::Result<()> {
-		let mut wtr = csv::Writer::from_path(&self.filename)?;
-		self.grid.to_csv(&mut wtr)?;
-		Ok(())
+	/// Write the grid to the file, returning a warning if it couldn't be written exactly.
+	///
+	/// Grids backed by the file are written in the background, and reported once they're done.
+	fn write(&mut self) -> io::Result<Option<Status>> {
+		if self.buf.job.is_some() {
+			return Err(io::Error::other("Already reading or writing the file"));
+		}
+		if let Some(table) = &mut self.buf.table {
+			table.write(
+				&self.buf.change_tracker.pending(&self.buf.grid),
+				&self.buf.grid,
+			)?;
+			return Ok(None);
+		}
+		if let Some(progress) = self.buf.grid.progress() {
+			return self.write_in_background(progress).map(|()| None);
+		}
+
+		let (text, overflow) = match (&self.buf.layout, self.buf.format) {
+			(Some(layout), Format::Fixed) => {
+				let written = layout.write(&self.buf.grid)?;
+				let overflow = written.first_overflow.map(|first| Status::Overflow {
+					cells: written.overflow,
+					first,
+				});
+				(written.text, overflow)
+			}
+			(_, format) => (format.write(&self.buf.grid, &self.buf.types)?, None),
+		};
+		let (bytes, unmappable) = self.buf.encoding.encode(&text);
+		if let Some(stdio) = &mut self.buf.stdio {
+			let mut encoder = self.buf.compression.encoder(Vec::new())?;
+			encoder.write_all(&bytes)?;
+			*stdio = encoder.finish()?;
+		} else {
+			let mut encoder = self
+				.buf
+				.compression
+				.encoder(File::create(&self.buf.filename)?)?;
+			encoder.write_all(&bytes)?;
+			encoder.finish()?;
+			self.update_stamp();
+		}
+
+		let unmappable = (unmappable > 0).then(|| Status::Encode {
+			encoding: self.buf.encoding.name().to_string(),
+			unmappable,
+		});
+		Ok(overflow.or(unmappable))
 	}
 
-	fn read(&mut self) -> io::Result<()> {
-		let rdr = csv::ReaderBuilder::new()
-			.has_headers(false)
-			.from_path(&self.filename)?;
-		let new = Grid::from_csv(rdr)?;
-		self.grid.replace(new).track(&mut self.change_tracker);
+	/// Start writing a grid backed by the file in the background.
+	fn write_in_background(&mut self, progress: Progress) -> io::Result<()> {
+		if !progress.done || progress.error.is_some() {
+			return Err(io::Error::other(
+				"Can't save until the whole file has been loaded",
+			));
+		}
+		let grid = self.buf.grid.clone();
+		let path = self.buf.filename.clone();
+		let total = grid.size().y as u64;
+		let task = Task::spawn("write", Unit::Rows, total, move |counter| {
+			write_replacing(&grid, &path, counter)
+		})?;
+		self.buf.job = Some(Job::Write {
+			task,
+			version: self.buf.change_tracker.version(),
+		});
 		Ok(())
 	}
 
+	/// Open the file as a grid that's read as it's displayed, if it's a large, uncompressed,
+	/// UTF-8 CSV file.
+	fn open_lazy(&self, compression: Compression) -> io::Result<Option<Grid>> {
+		if self.buf.format != Format::Csv
+			|| compression != Compression::None
+			|| self
+				.buf
+				.options
+				.encoding
+				.is_some_and(|e| e != TextEncoding::default())
+		{
+			return Ok(None);
+		}
+		let file = File::open(&self.buf.filename)?;
+		if file.metadata()?.len() < LAZY_SIZE {
+			return Ok(None);
+		}
+		let mut sample = Vec::new();
+		file.take(LAZY_SAMPLE).read_to_end(&mut sample)?;
+		if TextEncoding::detect(&sample) != TextEncoding::default() {
+			return Ok(None);
+		}
+		Grid::open_csv(&self.buf.filename).map(Some)
+	}
+
+	/// Replace the grid with the contents of the file, returning a warning if it couldn't be read exactly.
+	///
+	/// Files are read in the background, and any warning is reported once they're done.
+	fn read(&mut self) -> io::Result<Option<Status>> {
+		if self.buf.job.is_some() {
+			return Err(io::Error::other("Already reading or writing the file"));
+		}
+		if let Some(table) = &mut self.buf.table {
+			let new = table.read()?;
+			self.buf
+				.grid
+				.replace(new)
+				.track(&mut self.buf.change_tracker);
+			return Ok(None);
+		}
+		if let Some(workbook) = &self.buf.workbook {
+			let new = workbook.read()?;
+			self.buf
+				.grid
+				.replace(new)
+				.track(&mut self.buf.change_tracker);
+			return Ok(None);
+		}
+		if let Some(stdio) = &self.buf.stdio {
+			let compression = Compression::from_magic(stdio).unwrap_or_default();
+			let mut bytes = Vec::new();
+			compression
+				.decoder(stdio.as_slice())?
+				.read_to_end(&mut bytes)?;
+			let loaded = Loaded::decode(bytes, compression, self.buf.format, &self.buf.options)?;
+			return Ok(self.finish_read(loaded));
+		}
+
+		let compression = Compression::detect(&self.buf.filename)?;
+		if let Some(mut new) = self.open_lazy(compression)? {
+			debug!("Reading {:?} lazily", self.buf.filename);
+			new.refresh();
+			self.buf.compression = compression;
+			self.buf.encoding = TextEncoding::default();
+			self.buf.layout = None;
+			self.buf.types = Default::default();
+			self.buf
+				.grid
+				.replace(new)
+				.track(&mut self.buf.change_tracker);
+			self.update_stamp();
+			self.buf.indexing = true;
+			return Ok(None);
+		}
+
+		let file = File::open(&self.buf.filename)?;
+		let total = file.metadata()?.len();
+		let format = self.buf.format;
+		let options = self.buf.options.clone();
+		let task = Task::spawn("read", Unit::Bytes, total, move |counter| {
+			let mut bytes = Vec::new();
+			compression
+				.decoder(Counted::new(file, counter))?
+				.read_to_end(&mut bytes)?;
+			Loaded::decode(bytes, compression, format, &options)
+		})?;
+		self.buf.job = Some(Job::Read(task));
+		Ok(None)
+	}
+
+	/// Replace the grid with the contents read from the file, returning any warning.
+	fn finish_read(&mut self, loaded: Loaded) -> Option<Status> {
+		debug!(
+			"Read {:?} as {} {}",
+			self.buf.filename, self.buf.format, loaded.encoding
+		);
+		self.buf.compression = loaded.compression;
+		self.buf.encoding = loaded.encoding;
+		self.buf.layout = loaded.layout;
+		self.buf.types = loaded.types;
+		self.buf
+			.grid
+			.replace(loaded.grid)
+			.track(&mut self.buf.change_tracker);
+		self.update_stamp();
+		loaded.warning
+	}
+
 	pub fn draw(&mut self, t: &mut Terminal<impl Backend>) -> io::Result<()> {
 		let mut cursor_pos = None;
 		trace!("Beginning draw");
@@ -363,17 +2475,30 @@ impl Program {
 				let status_style = Style::default()
 					.add_modifier(Modifier::REVERSED)
 					.add_modifier(Modifier::BOLD);
-				let chord_msg = (!self.input_buf.is_empty())
-					.then(|| format!("Chord: <{}> ", self.input_buf))
-					.unwrap_or_default();
+				let chord_msg = match (self.input_buf.count(), self.input_buf.is_empty()) {
+					(None, true) => String::new(),
+					(None, false) => format!("Chord: {} ", self.input_buf),
+					(Some(count), true) => format!("Chord: {count} "),
+					(Some(count), false) => format!("Chord: {count} {} ", self.input_buf),
+				};
 
 				let state_msg = format!(
-					" {}{},{} {}x{}",
+					" {}{}{}{}{},{} {}x{}",
 					chord_msg,
-					self.selection.x + 1,
-					self.selection.y + 1,
-					self.grid.size().x,
-					self.grid.size().y
+					match &self.recording {
+						Some(recording) => format!("REC @{} ", recording.register),
+						None => String::new(),
+					},
+					if self.buf.follow { "FOLLOW " } else { "" },
+					if self.sync_scroll && self.panes.len() > 1 {
+						"SYNC "
+					} else {
+						""
+					},
+					self.buf.selection.x + 1,
+					self.buf.selection.y + 1,
+					self.buf.grid.size().x,
+					self.buf.grid.size().y
 				);
 
 				let [mode, status, state]: [Rect; 3] = Layout::default()
@@ -394,15 +2519,35 @@ impl Program {
 					EditCell(_) => " EDIT ",
 					Debug => " DBUG ",
 					Palette(_) => " CMDP ",
+					Recover { .. } => " SWAP ",
+					Export { .. } => " EXPT ",
+					SaveAs(_) => " SAVE ",
+					PickSheet(_) => " SHET ",
+					Changed(_) => " DISK ",
+					Buffers(_) => " BUFS ",
+					Conflicts(_) => " KEYS ",
+				};
+				let mode_msg = match self.vim_mode() {
+					Some(VimMode::Normal) => " NORM ",
+					Some(VimMode::Insert) => " INSR ",
+					Some(VimMode::Visual) => " VISU ",
+					None => mode_msg,
 				};
 				assert!(mode_msg.len() == mode.width as usize);
 				f.render_widget(Paragraph::new(mode_msg).style(status_style), mode);
 
-				if let Some(s) = &self.status_msg {
+				if let Some(msg) = self.progress_msg() {
+					f.render_widget(Paragraph::new(msg).style(status_style), status);
+				} else if let Some(s) = &self.status_msg {
 					f.render_widget(Paragraph::new(s).style(status_style), status);
+				} else if self.buffers.len() > 1 {
+					f.render_widget(
+						Paragraph::new(self.buffer_list()).style(status_style),
+						status,
+					);
 				} else {
 					f.render_widget(
-						Paragraph::new(self.filename.to_string_lossy()).style(status_style),
+						Paragraph::new(self.buf.filename.to_string_lossy()).style(status_style),
 						status,
 					);
 				}
@@ -411,60 +2556,121 @@ impl Program {
 
 			let size = main;
 
-			// sheet
-			// TODO: save to keep scrolling behavior
-			self.grid_state.select(Some(self.selection));
-			f.render_stateful_widget(GridView::new(&self.grid), size, &mut self.grid_state);
+			// sheets
+			let selected_range = self.selected_range();
+			let split = self.panes.len() > 1;
+			let mut grid_area = main;
+			for (index, area) in self.split.areas(main) {
+				let focused = index == self.focus;
+				let pane = &mut self.panes[index];
+				let buffer = if focused || pane.buffer == self.active {
+					&mut self.buf
+				} else {
+					&mut self.buffers[pane.buffer]
+				};
+				let name = buffer.name();
+				let (grid, selection, range, state, widths) = if focused {
+					let Buffer {
+						grid,
+						grid_state,
+						selection,
+						widths,
+						..
+					} = buffer;
+					(grid, *selection, selected_range, grid_state, &*widths)
+				} else {
+					let range = pane.anchor.and_then(|a| range_between(pane.selection, a));
+					(
+						&mut buffer.grid,
+						pane.selection,
+						range,
+						&mut pane.grid_state,
+						&buffer.widths,
+					)
+				};
+				let area = if split {
+					let title_style = if focused {
+						Style::default().add_modifier(Modifier::REVERSED)
+					} else {
+						Style::default()
+					};
+					let block = Block::default()
+						.borders(Borders::ALL)
+						.title(Span::styled(name, title_style));
+					let inner = block.inner(area);
+					f.render_widget(block, area);
+					inner
+				} else {
+					area
+				};
+				if focused {
+					grid_area = area;
+				}
+				let rows = area.height as usize;
+				grid.load(selection.y.saturating_sub(rows)..selection.y + rows);
+				state.select(Some(selection));
+				state.select_range(range);
+				f.render_stateful_widget(GridView::new(grid).widths(widths), area, state);
+			}
 
 			use ViewState::*;
 			match &mut self.view {
 				Normal => {
 					// chord options
-					if !self.input_buf.is_empty() {
-						if let Some(b) = self
-							.bindings
-							.get(&self.input_buf)
-							.and_then(|n| n.bindings())
-						{
-							let text: Vec<_> = b
-								.singles()
-								.map(|(input, a)| {
-									Spans::from(vec![
-										Span::styled(input.to_string(), styles::keybind()),
-										Span::raw(" "),
-										Span::raw(format!("{a:?}")),
-									])
-								})
-								.collect();
-							let width = min(
-								size.width,
-								text.iter().map(|s| s.width()).max().unwrap_or_default() as u16 + 2,
-							);
-							let height = min(size.height, text.len() as u16 + 2);
-							let bounds = Rect {
-								x: size.right() - width,
-								y: size.bottom() - height,
-								width,
-								height,
-							};
-							f.render_widget(Clear, bounds);
-							f.render_widget(
-								Paragraph::new(text).block(
-									Block::default()
-										.title(Span::styled(
-											format!(" {} ", self.input_buf),
-											styles::keybind(),
-										))
-										.borders(Borders::ALL),
-								),
-								bounds,
-							);
-						}
+					let bindings = match &self.vim {
+						Some(vim) => vim.bindings(self.buf.anchor.is_some()),
+						None => &self.bindings,
+					};
+					if let Some(BindNode::Chord { name, bindings: b }) = bindings
+						.get(&self.input_buf)
+						.filter(|_| !self.input_buf.is_empty())
+					{
+						let mut text: Vec<_> = b
+							.singles()
+							.map(|(input, a)| {
+								Spans::from(vec![
+									Span::styled(input.to_string(), styles::keybind()),
+									Span::raw(" "),
+									Span::raw(format!("{a:?}")),
+								])
+							})
+							.collect();
+						text.push(Spans::from(vec![
+							Span::styled("<Esc>", styles::keybind()),
+							Span::raw(" Cancel"),
+						]));
+						let title =
+							Span::styled(format!(" {} {name} ", self.input_buf), styles::keybind());
+						let width = text
+							.iter()
+							.map(|s| s.width())
+							.chain([title.width()])
+							.max()
+							.unwrap_or_default();
+						let width = min(size.width, width as u16 + 2);
+						let height = min(size.height, text.len() as u16 + 2);
+						let bounds = Rect {
+							x: size.right() - width,
+							y: size.bottom() - height,
+							width,
+							height,
+						};
+						f.render_widget(Clear, bounds);
+						f.render_widget(
+							Paragraph::new(text)
+								.block(Block::default().title(title).borders(Borders::ALL)),
+							bounds,
+						);
 					}
 				}
 				EditCell(editor) => {
 					// draw edit popup
-					let size = self.grid_state.selected_area().unwrap();
+					// an empty grid draws no cells, so edit at its corner
+					let size = self.buf.grid_state.selected_area().unwrap_or(Rect {
+						width: min(grid_area.width, DEFAULT_WIDTH),
+						height: min(grid_area.height, 1),
+						..grid_area
+					});
 					f.render_widget(Clear, size);
 					f.render_stateful_widget(
 						EditView::default().style(styles::grid()),
@@ -483,6 +2689,36 @@ impl Program {
 					f.render_stateful_widget(PaletteView::default(), size, state);
 					cursor_pos = Some(state.cursor(size));
 				}
+				Export { input, .. } | SaveAs(input) => {
+					let margins = Margin {
+						horizontal: size.width.saturating_sub(64) / 2,
+						vertical: 0,
+					};
+					let mut area = size.inner(&margins);
+					area.height = min(area.height, InputView::HEIGHT);
+					f.render_stateful_widget(InputView::default(), area, input);
+					cursor_pos = Some(input.cursor(area));
+				}
+				Recover { prompt, .. } => {
+					let area = prompt_area(size, |width| prompt.height(width));
+					f.render_stateful_widget(PromptView::default(), area, prompt);
+				}
+				Buffers(prompt) => {
+					let area = prompt_area(size, |width| prompt.height(width));
+					f.render_stateful_widget(PromptView::default(), area, prompt);
+				}
+				Conflicts(prompt) => {
+					let area = prompt_area(size, |width| prompt.height(width));
+					f.render_stateful_widget(PromptView::default(), area, prompt);
+				}
+				Changed(prompt) => {
+					let area = prompt_area(size, |width| prompt.height(width));
+					f.render_stateful_widget(PromptView::default(), area, prompt);
+				}
+				PickSheet(prompt) => {
+					let area = prompt_area(size, |width| prompt.height(width));
+					f.render_stateful_widget(PromptView::default(), area, prompt);
+				}
 				Debug => {
 					let border = Block::default().title("Logs").borders(Borders::ALL);
 					let inner = border.inner(size);
@@ -505,6 +2741,50 @@ impl Program {
 	}
 }
 
+/// Cells between two opposite corners
+fn range_between(XY { x, y }: XY<usize>, corner: XY<usize>) -> Option<MyRect<usize>> {
+	Some(MyRect {
+		x: min(x, corner.x),
+		y: min(y, corner.y),
+		width: x.abs_diff(corner.x) + 1,
+		height: y.abs_diff(corner.y) + 1,
+	})
+}
+
+/// Write a grid backed by the file at `path` through a temporary file, since rows that weren't
+/// changed are still read from the original while writing.
+fn write_replacing(grid: &Grid, path: &Path, counter: &Counter) -> io::Result<()> {
+	let mut tmp = path.to_owned().into_os_string();
+	tmp.push(".sht-tmp");
+	let tmp = PathBuf::from(tmp);
+	let result = (|| {
+		let mut wtr = csv::Writer::from_writer(io::BufWriter::new(File::create(&tmp)?));
+		for row in grid.rows() {
+			counter.check()?;
+			wtr.write_record(row?.iter())?;
+			counter.add(1);
+		}
+		wtr.flush()?;
+		fs::rename(&tmp, path)
+	})();
+	if result.is_err() {
+		let _ = fs::remove_file(&tmp);
+	}
+	result
+}
+
+/// Area centered in `size` for a prompt, given its height at a width
+fn prompt_area(size: Rect, height: impl FnOnce(u16) -> u16) -> Rect {
+	let margins = Margin {
+		horizontal: size.width.saturating_sub(64) / 2,
+		vertical: 0,
+	};
+	let mut area = size.inner(&margins);
+	area.height = min(area.height, height(area.width));
+	area.y += size.height.saturating_sub(area.height) / 2;
+	area
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 enum ViewState {
 	/// Moving around the sheet
@@ -514,4 +2794,557 @@ enum ViewState {
 	EditCell(EditState),
 	Debug,
 	Palette(PaletteState),
+	/// Choosing where to export a table
+	Export {
+		format: TableFormat,
+		input: InputState,
+	},
+	/// Choosing where to save a sheet that can't be written back
+	SaveAs(InputState),
+	/// Choosing which sheet of a workbook to view
+	PickSheet(PromptState<String>),
+	/// Deciding what to do with a swap file from a previous session
+	Recover {
+		prompt: PromptState<Recovery>,
+		swap: Swap,
+	},
+	/// Deciding what to do about another program changing the file
+	Changed(PromptState<DiskChange>),
+	/// Choosing which open buffer to show
+	Buffers(PromptState<usize>),
+	/// Showing key bindings that replaced others
+	Conflicts(PromptState<()>),
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum Recovery {
+	Recover,
+	Discard,
+	Diff,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+enum DiskChange {
+	Reload,
+	/// Keep the grid as it is, overwriting the file on the next write
+	Keep,
+	/// Write the grid to a different file
+	SaveAs,
+}
+
+/// A number of at least 1, for a count, row, column, or width
+fn parse_number(s: &str) -> Result<usize, String> {
+	match s.parse() {
+		Ok(0) | Err(_) => Err(format!("`{s}` isn't a number from 1 up")),
+		Ok(n) => Ok(n),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{env, process, thread};
+
+	use tui::backend::TestBackend;
+
+	use super::*;
+
+	/// Temporary file, removed along with any swap file when dropped
+	struct TempFile(PathBuf);
+
+	impl TempFile {
+		/// File in the temporary directory, a CSV file unless `name` has an extension
+		fn new(name: &str, contents: &str) -> Self {
+			let (stem, ext) = name.split_once('.').unwrap_or((name, "csv"));
+			let path = env::temp_dir().join(format!("sht_{stem}_{}.{ext}", process::id()));
+			fs::write(&path, contents).unwrap();
+			Self(path)
+		}
+	}
+
+	impl Drop for TempFile {
+		fn drop(&mut self) {
+			let _ = fs::remove_file(&self.0);
+			let _ = swap::remove(&swap::path_for(&self.0));
+		}
+	}
+
+	/// Program editing `files`, once they've been read
+	fn open(files: &[&TempFile]) -> Program {
+		let paths = files.iter().map(|f| f.0.clone()).collect();
+		let mut program = Program::from_paths(paths, FileOptions::default()).unwrap();
+		settle(&mut program);
+		program
+	}
+
+	/// Program editing `bytes` as if they were piped to `sht -`
+	fn piped(bytes: &[u8]) -> Program {
+		let mut program = Program {
+			buffers: vec![Buffer::default()],
+			panes: vec![Pane::default()],
+			..Default::default()
+		};
+		program.buf = Buffer {
+			filename: STDIO_PATH.into(),
+			stdio: Some(bytes.to_vec()),
+			..Default::default()
+		};
+		program.read().unwrap();
+		program.buf.change_tracker = Default::default();
+		program
+	}
+
+	/// Let reads and writes in the background finish.
+	fn settle(program: &mut Program) {
+		program.tick();
+		while program.iter_buffers().any(Buffer::is_busy) {
+			thread::sleep(Duration::from_millis(5));
+			program.tick();
+		}
+	}
+
+	/// Type `keys`, written as in the config.
+	fn press(program: &mut Program, keys: &str) {
+		for input in keys.parse::<InputBuffer>().unwrap() {
+			program.handle_input(input).unwrap();
+		}
+	}
+
+	/// Cells of the shown buffer, as lines of comma-separated cells
+	fn cells(program: &Program) -> String {
+		let rows = program.buf.grid.to_vec().unwrap();
+		rows.iter()
+			.map(|row| row.join(","))
+			.collect::<Vec<_>>()
+			.join("\n")
+	}
+
+	#[test]
+	fn edits_stdin_and_writes_stdout() {
+		let mut program = piped(b"a,b\n1,2\n");
+		assert_eq!("a,b\n1,2", cells(&program));
+		assert_eq!(None, program.swap_path());
+
+		press(&mut program, "<Enter> x y <Enter>");
+		assert_eq!("xy,b\n1,2", cells(&program));
+		// passed through unchanged until written
+		assert_eq!(Some(&b"a,b\n1,2\n"[..]), program.stdout());
+		press(&mut program, "<C-s>");
+		assert_eq!(Some(&b"xy,b\n1,2\n"[..]), program.stdout());
+		assert!(!program.buf.change_tracker.is_modified());
+	}
+
+	#[test]
+	fn keeps_buffers_apart() {
+		let first = TempFile::new("first", "a,b\nc,d\n");
+		let second = TempFile::new("second", "e,f\n");
+		let mut program = open(&[&first, &second]);
+		assert_eq!("a,b\nc,d", cells(&program));
+
+		press(&mut program, "j <Enter> x <Enter> <C-PageDown>");
+		assert_eq!("e,f", cells(&program));
+		assert_eq!(XY { x: 0, y: 0 }, program.buf.selection);
+		press(&mut program, "<Enter> y <Enter> <C-s> <C-PageUp>");
+		assert_eq!("a,b\nx,d", cells(&program));
+		// Enter moved down after the edit
+		assert_eq!(XY { x: 0, y: 2 }, program.buf.selection);
+
+		// only the shown buffer is written, and undo stays within each buffer
+		assert_eq!("a,b\nc,d\n", fs::read_to_string(&first.0).unwrap());
+		assert_eq!("y,f\n", fs::read_to_string(&second.0).unwrap());
+		press(&mut program, "<C-z>");
+		assert_eq!("a,b\nc,d", cells(&program));
+		assert_eq!(
+			"y,f",
+			program.buffers[1].grid.to_vec().unwrap()[0].join(",")
+		);
+
+		// the list marks buffers with unsaved changes, and shows the one chosen
+		press(&mut program, "<C-y> <A-b>");
+		let ViewState::Buffers(prompt) = &program.view else {
+			panic!("expected the buffer list, got {:?}", program.view);
+		};
+		assert!(format!("{prompt:?}").contains("(modified)"));
+		press(&mut program, "2");
+		assert_eq!(1, program.active);
+		assert_eq!("y,f", cells(&program));
+	}
+
+	#[test]
+	fn follows_only_csv_files() {
+		let options = FileOptions {
+			follow: true,
+			..Default::default()
+		};
+		let csv = TempFile::new("follow", "a\n");
+		let json = TempFile::new("follow.json", "[{\"a\": 1}]");
+		let mut program = Program::from_paths(vec![csv.0.clone()], options.clone()).unwrap();
+		settle(&mut program);
+		assert!(program.buf.follow);
+
+		let mut program = Program::from_paths(vec![json.0.clone()], options).unwrap();
+		settle(&mut program);
+		assert!(!program.buf.follow);
+		assert!(matches!(program.status_msg, Some(Status::Follow(Err(_)))));
+	}
+
+	#[test]
+	fn asks_before_overwriting_changes_on_disk() {
+		let file = TempFile::new("overwrite", "a,b\n");
+		let mut program = open(&[&file]);
+		press(&mut program, "<Enter> x <Enter>");
+		fs::write(&file.0, "theirs,b,c\n").unwrap();
+
+		press(&mut program, "<C-s>");
+		assert!(matches!(program.view, ViewState::Changed(_)));
+		assert_eq!("theirs,b,c\n", fs::read_to_string(&file.0).unwrap());
+
+		// keep ours, then save over theirs
+		press(&mut program, "k <C-s>");
+		settle(&mut program);
+		assert!(matches!(program.view, ViewState::Normal));
+		assert_eq!("x,b\n", fs::read_to_string(&file.0).unwrap());
+	}
+
+	#[test]
+	fn refuses_to_save_over_the_workbook() {
+		let file = TempFile::new("sheet", "a,b\n");
+		let book = TempFile::new("book.xlsx", "workbook");
+		let mut program = open(&[&file]);
+		let workbook = serde_json::json!({ "path": book.0, "sheet": "Sheet1" });
+		program.buf.workbook = Some(serde_json::from_value(workbook).unwrap());
+		let path = book.0.to_string_lossy();
+
+		program.view = ViewState::SaveAs(InputState::new("", &path));
+		press(&mut program, "<Enter>");
+		assert!(matches!(program.status_msg, Some(Status::Write(_, Err(_)))));
+		assert!(program.buf.workbook.is_some());
+
+		program.view = ViewState::Export {
+			format: TableFormat::Markdown,
+			input: InputState::new("", &path),
+		};
+		press(&mut program, "<Enter>");
+		assert!(matches!(
+			program.status_msg,
+			Some(Status::Export(_, Err(_)))
+		));
+		assert_eq!("workbook", fs::read_to_string(&book.0).unwrap());
+	}
+
+	#[test]
+	fn notices_changes_to_buffers_not_shown() {
+		let first = TempFile::new("shown", "a\n");
+		let second = TempFile::new("hidden", "b\n");
+		let mut program = open(&[&first, &second]);
+		fs::write(&second.0, "b,changed\n").unwrap();
+
+		let start = Instant::now();
+		while !program.buffers[1].disk_changed && start.elapsed() < Duration::from_secs(5) {
+			thread::sleep(Duration::from_millis(10));
+			program.tick();
+		}
+		assert!(program.buffers[1].disk_changed);
+		assert!(matches!(program.view, ViewState::Normal));
+
+		press(&mut program, "<C-PageDown>");
+		program.tick();
+		assert!(matches!(program.view, ViewState::Changed(_)));
+		press(&mut program, "r");
+		settle(&mut program);
+		assert_eq!("b,changed", cells(&program));
+	}
+
+	#[test]
+	fn switches_vim_modes() {
+		let file = TempFile::new("vim", "a,b\nc,d\n");
+		let mut program = open(&[&file]);
+		program.handle_action(Action::ToggleVim, None).unwrap();
+		assert_eq!(Some(VimMode::Normal), program.vim_mode());
+
+		// i edits from the start of the cell, and Esc keeps the edit
+		press(&mut program, "i");
+		assert_eq!(Some(VimMode::Insert), program.vim_mode());
+		press(&mut program, "x <Esc>");
+		assert_eq!(Some(VimMode::Normal), program.vim_mode());
+		assert_eq!("xa,b\nc,d", cells(&program));
+
+		// v selects, and d clears the selection
+		press(&mut program, "j v l");
+		assert_eq!(Some(VimMode::Visual), program.vim_mode());
+		press(&mut program, "d");
+		assert_eq!(Some(VimMode::Normal), program.vim_mode());
+		assert_eq!("xa,b\n,", cells(&program));
+
+		press(&mut program, "u g g d d");
+		assert_eq!("c,d", cells(&program));
+		program.handle_action(Action::ToggleVim, None).unwrap();
+		assert_eq!(None, program.vim_mode());
+	}
+
+	#[test]
+	fn edits_past_the_edge_in_vim_insert_mode() {
+		let file = TempFile::new("vim_edge", "a\n");
+		let mut program = open(&[&file]);
+		program.handle_action(Action::ToggleVim, None).unwrap();
+		press(&mut program, "j l a x <Esc>");
+		assert_eq!("a,\n,x", cells(&program));
+		press(&mut program, "u");
+		assert_eq!("a", cells(&program));
+	}
+
+	#[test]
+	fn keeps_config_bindings_when_switching_to_vim() {
+		let mut program = Program::default();
+		let config = Config::parse(
+			"[[bind]]\nkeys = \"u\"\naction = \"Redo\"\n\n[[bind]]\nkeys = \"<C-q>\"\naction = \"Quit\"",
+		)
+		.unwrap();
+		program.apply_config(config);
+		for _ in 0..3 {
+			program.toggle_vim();
+		}
+		let bindings = program.active_bindings();
+		assert_eq!(Some(&Action::Redo), bindings.get_single(Input::from('u')));
+		let ctrl_q: Input = "<C-q>".parse().unwrap();
+		assert_eq!(Some(&Action::Quit), bindings.get_single(ctrl_q));
+
+		program.list_conflicts();
+		let ViewState::Conflicts(prompt) = &program.view else {
+			panic!("expected the conflicts, got {:?}", program.view);
+		};
+		assert!(format!("{prompt:?}").contains("u (Redo) replaces u (Undo)"));
+	}
+
+	#[test]
+	fn repeats_actions_by_count() {
+		let file = TempFile::new("count", "0\n1\n2\n3\n4\n5\n");
+		let mut program = open(&[&file]);
+		press(&mut program, "2 j 3 <A--> r");
+		assert_eq!(XY { x: 0, y: 2 }, program.buf.selection);
+		assert_eq!("0\n1\n5", cells(&program));
+		// as one change
+		press(&mut program, "<C-z>");
+		assert_eq!("0\n1\n2\n3\n4\n5", cells(&program));
+
+		program.handle_action(Action::ToggleVim, None).unwrap();
+		press(&mut program, "2 d d");
+		assert_eq!("0\n1\n4\n5", cells(&program));
+		press(&mut program, "1 2 p");
+		assert_eq!(28, program.buf.grid.size().y);
+		press(&mut program, "u u 3 u");
+		assert_eq!("0\n1\n2\n3\n4\n5", cells(&program));
+	}
+
+	#[test]
+	fn clamps_counts_at_the_edge() {
+		let file = TempFile::new("clamp", "a,b,c\n");
+		let mut program = open(&[&file]);
+		// deleting columns only depends on the column, even below the last row
+		press(&mut program, "l j 5 <A--> c");
+		assert_eq!("a", cells(&program));
+		press(&mut program, "<C-z>");
+		assert_eq!("a,b,c", cells(&program));
+
+		// deleting rows only depends on the row, even right of the last column
+		let file = TempFile::new("clamp_rows", "a\nb\nc\n");
+		let mut program = open(&[&file]);
+		program.handle_action(Action::ToggleVim, None).unwrap();
+		press(&mut program, "l l j 9 d d");
+		assert_eq!("a", cells(&program));
+		press(&mut program, "P");
+		assert_eq!("a\nb\nc", cells(&program));
+	}
+
+	#[test]
+	fn grows_the_grid_when_typing_past_the_edge() {
+		let file = TempFile::new("grow", "a,b\n");
+		let mut program = open(&[&file]);
+		press(&mut program, "l l x <Enter>");
+		assert_eq!("a,b,x", cells(&program));
+		press(&mut program, "k j j y <Tab>");
+		assert_eq!("a,b,x\n,,\n,,y", cells(&program));
+		press(&mut program, "<C-z>");
+		assert_eq!("a,b,x", cells(&program));
+	}
+
+	#[test]
+	fn types_into_an_empty_grid() {
+		let file = TempFile::new("empty", "");
+		let mut program = open(&[&file]);
+		assert_eq!(XY { x: 0, y: 0 }, program.buf.grid.size());
+		let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+		press(&mut program, "x");
+		assert!(matches!(program.view, ViewState::EditCell(_)));
+		program.draw(&mut terminal).unwrap();
+		press(&mut program, "y <Enter>");
+		assert_eq!("xy", cells(&program));
+		program.draw(&mut terminal).unwrap();
+	}
+
+	#[test]
+	fn cancels_chords_with_esc() {
+		let file = TempFile::new("esc", "a\nb\n");
+		let mut program = open(&[&file]);
+		// r after the cancelled chord is typed into the cell instead of deleting the row
+		press(&mut program, "<A--> <Esc>");
+		assert!(program.input_buf.is_empty());
+		press(&mut program, "r");
+		assert!(matches!(program.view, ViewState::EditCell(_)));
+		press(&mut program, "<Esc> 3 <Esc>");
+		assert!(!program.input_buf.is_pending());
+		assert_eq!("a\nb", cells(&program));
+
+		// Esc only cancels the chord, staying in visual mode
+		program.handle_action(Action::ToggleVim, None).unwrap();
+		press(&mut program, "v j g <Esc>");
+		assert!(!program.input_buf.is_pending());
+		assert_eq!(Some(VimMode::Visual), program.vim_mode());
+		press(&mut program, "<Esc>");
+		assert_eq!(Some(VimMode::Normal), program.vim_mode());
+	}
+
+	#[test]
+	fn drops_chords_after_the_timeout() {
+		let file = TempFile::new("timeout", "a\nb\n");
+		let mut program = open(&[&file]);
+		program.chord_timeout = Some(Duration::from_millis(200));
+		press(&mut program, "<A-->");
+		program.tick();
+		assert!(program.input_buf.is_pending());
+		thread::sleep(Duration::from_millis(250));
+		program.tick();
+		assert!(!program.input_buf.is_pending());
+		press(&mut program, "r");
+		assert!(matches!(program.view, ViewState::EditCell(_)));
+		assert_eq!("a\nb", cells(&program));
+	}
+
+	#[test]
+	fn repeats_the_last_change() {
+		let file = TempFile::new("repeat", "a\nb\nc\nd\ne\nf\ng\n");
+		let mut program = open(&[&file]);
+		// typed text is entered again
+		press(&mut program, "<Enter> x y <Enter> <F4>");
+		assert_eq!("xy\nxy\nc\nd\ne\nf\ng", cells(&program));
+
+		// a count replaces the last one, and is kept for the next repeat
+		press(&mut program, "j <A--> r 2 <F4>");
+		assert_eq!("xy\nxy\nf\ng", cells(&program));
+		press(&mut program, "<F4>");
+		assert_eq!("xy\nxy", cells(&program));
+		press(&mut program, "<C-z>");
+		assert_eq!("xy\nxy\nf\ng", cells(&program));
+
+		// switching to vim keeps the change to repeat
+		program.handle_action(Action::ToggleVim, None).unwrap();
+		press(&mut program, "g g .");
+		assert_eq!("f\ng", cells(&program));
+		press(&mut program, "u c z <Esc> j .");
+		assert_eq!("z\nz\nf\ng", cells(&program));
+	}
+
+	#[test]
+	fn plays_macros() {
+		let file = TempFile::new("macro", &"0\n1\n2\n3\n4\n5\n6\n7\n8\n9\n".repeat(2));
+		let mut program = open(&[&file]);
+		let rows = cells(&program);
+		for (register, keys) in [
+			('a', "<A--> r"),
+			('b', "<A-@> a j"),
+			// plays itself until too many are playing
+			('r', "<A--> r <A-@> r"),
+		] {
+			program.macros.insert(register, keys.parse().unwrap());
+		}
+
+		// undone at once, however many times it's played
+		press(&mut program, "3 <A-@> a");
+		assert_eq!(17, program.buf.grid.size().y);
+		press(&mut program, "<C-z>");
+		assert_eq!(rows, cells(&program));
+
+		press(&mut program, "2 <A-@> b");
+		assert_eq!(18, program.buf.grid.size().y);
+		assert_eq!(XY { x: 0, y: 2 }, program.buf.selection);
+		assert_eq!(
+			Some("3"),
+			program.buf.grid.get(XY { x: 0, y: 1 }).map(String::as_str)
+		);
+		press(&mut program, "<C-z>");
+		assert_eq!(rows, cells(&program));
+
+		press(&mut program, "<A-@> r");
+		assert_eq!(20 - MAX_MACRO_DEPTH, program.buf.grid.size().y);
+		press(&mut program, "<C-z>");
+		assert_eq!(rows, cells(&program));
+	}
+
+	#[test]
+	fn undoes_macros_in_each_buffer_at_once() {
+		let first = TempFile::new("macro_first", "a,b\n");
+		let second = TempFile::new("macro_second", "c,d\n");
+		let mut program = open(&[&first, &second]);
+		let keys = "<Enter> x <Tab> y <Enter> <C-PageDown> <Enter> z <Tab> w <Enter> <C-PageUp>";
+		program.macros.insert('a', keys.parse().unwrap());
+		press(&mut program, "<A-@> a");
+		assert_eq!("x,y", cells(&program));
+		assert_eq!(1, program.buffers[1].change_tracker.undo_len());
+
+		press(&mut program, "<C-z>");
+		assert_eq!("a,b", cells(&program));
+		press(&mut program, "<C-PageDown>");
+		assert_eq!("z,w", cells(&program));
+		press(&mut program, "<C-z>");
+		assert_eq!("c,d", cells(&program));
+	}
+
+	#[test]
+	fn sorts_below_the_header() {
+		let file = TempFile::new("sort", "b,2\nc,1\na,3\n");
+		let mut program = open(&[&file]);
+		program
+			.handle_action(Action::SortAscending, Some(2))
+			.unwrap();
+		assert_eq!("c,1\nb,2\na,3", cells(&program));
+
+		let file = TempFile::new("sort.json", r#"[{"n": "b"}, {"n": "c"}, {"n": "a"}]"#);
+		let mut program = open(&[&file]);
+		program
+			.handle_action(Action::SortDescending, Some(1))
+			.unwrap();
+		assert_eq!("n\nc\nb\na", cells(&program));
+		// even when the selection includes it
+		press(&mut program, "v j j j");
+		program
+			.handle_action(Action::SortAscending, Some(1))
+			.unwrap();
+		assert_eq!("n\na\nb\nc", cells(&program));
+	}
+
+	#[test]
+	fn keeps_widths_with_their_columns() {
+		let file = TempFile::new("widths", "a,b,c\n");
+		let mut program = open(&[&file]);
+		press(&mut program, "l");
+		program.handle_action(Action::SetWidth, Some(5)).unwrap();
+		press(&mut program, "l");
+		program.handle_action(Action::SetWidth, Some(7)).unwrap();
+
+		press(&mut program, "h h");
+		program.handle_action(Action::InsertCol, None).unwrap();
+		assert_eq!(BTreeMap::from([(2, 5), (3, 7)]), program.buf.widths);
+		press(&mut program, "l l");
+		program.handle_action(Action::DeleteCol, None).unwrap();
+		assert_eq!(BTreeMap::from([(2, 7)]), program.buf.widths);
+		assert_eq!(",a,c", cells(&program));
+
+		program.handle_action(Action::Undo, None).unwrap();
+		assert_eq!(BTreeMap::from([(3, 7)]), program.buf.widths);
+		program.handle_action(Action::Undo, None).unwrap();
+		assert_eq!(BTreeMap::from([(2, 7)]), program.buf.widths);
+		assert_eq!("a,b,c", cells(&program));
+		program.handle_action(Action::Redo, None).unwrap();
+		assert_eq!(BTreeMap::from([(3, 7)]), program.buf.widths);
+		assert_eq!(",a,b,c", cells(&program));
+	}
 }
@@ -4,6 +4,8 @@ use enum_iterator::Sequence;
 use serde::{Deserialize, Serialize};
 use strum::{EnumMessage, IntoStaticStr};
 
+use crate::format::TableFormat;
+
 pub enum ExternalAction {
 	Quit,
 }
@@ -26,19 +28,29 @@ pub enum ExternalAction {
 pub enum Action {
 	/// Move the cursor
 	Move(Direction),
+	/// Move the cursor a screen
 	Jump(Direction),
+	/// Go to a cell
 	GoTo,
+	/// Go to the first cell
 	Home,
+	/// Go to the last cell
 	End,
+	/// Go to the top of the column
 	HomeCol,
+	/// Go to the bottom of the column
 	EndCol,
+	/// Go to the start of the row
 	HomeRow,
+	/// Go to the end of the row
 	EndRow,
 	/// Edit the current cell
 	Edit,
+	/// Edit the current cell from the start
+	EditFromStart,
 	/// Replace the current cell
 	Replace,
-	/// Clear the current cell
+	/// Clear the current cell, or the selected cells
 	Clear,
 	/// Delete column of current cursor
 	DeleteCol,
@@ -48,37 +60,126 @@ pub enum Action {
 	InsertCol,
 	/// Insert row of current cursor
 	InsertRow,
+	/// Insert a number of rows at the cursor
+	InsertRows,
+	/// Sort the rows, or selected rows, by a column, smallest first
+	SortAscending,
+	/// Sort the rows, or selected rows, by a column, largest first
+	SortDescending,
+	/// Set the width of the current column
+	SetWidth,
+	/// Start or stop selecting a range of cells
+	Select,
+	/// Copy the current row, or the selected cells, to paste later
+	Yank,
+	/// Paste copied rows below the current one, or copied cells at the cursor
+	PasteBelow,
+	/// Paste copied rows above the current one, or copied cells at the cursor
+	PasteAbove,
+	/// Export the selection or sheet as a table
+	Export(TableFormat),
+	/// Copy the selection or sheet to the clipboard as a table
+	CopyAs(TableFormat),
+	/// Undo the last change
 	Undo,
+	/// Redo the last undone change
 	Redo,
+	/// Make the last change again, like setting a cell to what was typed into the last one
+	Repeat,
+	/// Start recording keys into a macro, or stop recording
+	RecordMacro,
+	/// Play back the keys recorded into a macro
+	PlayMacro,
 	/// Write state to original file
 	Write,
 	/// Reload the original file, dropping any unsaved changes
 	Read,
 	/// Quit the program
 	Quit,
+	/// Stop reading or writing the file in the background
+	Cancel,
+	/// Start or stop adding rows appended to the file, like `tail -f`
+	ToggleFollow,
+	/// Show the next open file
+	NextBuffer,
+	/// Show the previous open file
+	PrevBuffer,
+	/// Choose an open file to show
+	ListBuffers,
+	/// Split the pane in two, one above the other
+	SplitHorizontal,
+	/// Split the pane in two, side by side
+	SplitVertical,
+	/// Close the pane, unless it's the only one
+	ClosePane,
+	/// Move to the next pane
+	NextPane,
+	/// Move to the previous pane
+	PrevPane,
+	/// Start or stop moving every pane along with the current one
+	ToggleSyncScroll,
+	/// Show key bindings from the config that replaced others
+	ListConflicts,
+	/// Switch between the default and vim-style key bindings
+	ToggleVim,
+	/// Show or hide the log
 	ToggleDebug,
+	/// Write the state of the program to a temporary file
 	DumpState,
+	/// Show or hide the command palette
 	TogglePalette,
 }
 
 impl Action {
-	// TODO: make detailed for Move and other sub-variants, probably with mem::discriminant and lazy_static?
-	pub fn desc(&self) -> &'static str {
+	pub fn desc(&self) -> String {
+		use Action::*;
+		match self {
+			Move(d) | Jump(d) => format!("{} {d}", self.doc()),
+			Export(format) => format!("Export the selection or sheet as {}", format.name()),
+			CopyAs(format) => format!("Copy the selection or sheet as {}", format.name()),
+			_ => self.doc().to_string(),
+		}
+	}
+
+	fn doc(&self) -> &'static str {
 		self.get_documentation().unwrap_or(self.into())
 	}
-}
 
-pub struct Doc<'a>(&'a Action);
+	/// What to ask for, if the action takes an argument typed after choosing it
+	pub fn argument(&self) -> Option<&'static str> {
+		use Action::*;
+		Some(match self {
+			GoTo => "Row, or column and row, like 12 or 3,12",
+			InsertRows => "Number of rows",
+			SortAscending | SortDescending => "Column number, or nothing for the current one",
+			SetWidth => "Width in characters, or nothing to fit the contents",
+			_ => return None,
+		})
+	}
 
-impl<'a> Display for Doc<'a> {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		if let Some(d) = self.0.get_documentation() {
-			write!(f, "{}", d)?;
-		} else {
-			write!(f, "{:?}", self.0)?;
-		}
+	/// Whether the action changes the grid, so it can be repeated later
+	pub fn changes(&self) -> bool {
+		use Action::*;
+		matches!(
+			self,
+			Clear | DeleteCol | DeleteRow | InsertCol | InsertRow | PasteBelow | PasteAbove
+		)
+	}
 
-		Ok(())
+	/// Whether a count before the action repeats it
+	pub fn repeats(&self) -> bool {
+		use Action::*;
+		matches!(
+			self,
+			Move(_)
+				| Jump(_) | DeleteCol
+				| DeleteRow | InsertCol
+				| InsertRow | PasteBelow
+				| PasteAbove | Undo
+				| Redo | NextBuffer
+				| PrevBuffer | NextPane
+				| PrevPane
+		)
 	}
 }
 
@@ -91,3 +192,15 @@ pub enum Direction {
 	Right,
 	Up,
 }
+
+impl Display for Direction {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let name = match self {
+			Direction::Down => "down",
+			Direction::Left => "left",
+			Direction::Right => "right",
+			Direction::Up => "up",
+		};
+		write!(f, "{name}")
+	}
+}
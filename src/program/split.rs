@@ -0,0 +1,152 @@
+//! Arrangement of panes in the main area of the screen.
+use serde::{Deserialize, Serialize};
+use tui::layout::{Constraint, Direction, Layout, Rect};
+
+/// Panes, identified by index, arranged by recursively splitting the screen
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Split {
+	Pane(usize),
+	/// Panes sharing the space equally, one above the other if `stacked` or else side by side
+	Split {
+		stacked: bool,
+		children: Vec<Split>,
+	},
+}
+
+impl Default for Split {
+	fn default() -> Self {
+		Split::Pane(0)
+	}
+}
+
+impl Split {
+	/// Every pane, from top left to bottom right
+	pub fn panes(&self) -> Vec<usize> {
+		match self {
+			Split::Pane(p) => vec![*p],
+			Split::Split { children, .. } => children.iter().flat_map(Split::panes).collect(),
+		}
+	}
+
+	/// Put `new` after `pane`, sharing its space, returning whether `pane` was found.
+	pub fn split(&mut self, pane: usize, new: usize, stacked: bool) -> bool {
+		match self {
+			Split::Pane(p) if *p == pane => {
+				*self = Split::Split {
+					stacked,
+					children: vec![Split::Pane(pane), Split::Pane(new)],
+				};
+				true
+			}
+			Split::Pane(_) => false,
+			Split::Split {
+				stacked: s,
+				children,
+			} => {
+				if *s == stacked {
+					if let Some(i) = children.iter().position(|c| *c == Split::Pane(pane)) {
+						children.insert(i + 1, Split::Pane(new));
+						return true;
+					}
+				}
+				children.iter_mut().any(|c| c.split(pane, new, stacked))
+			}
+		}
+	}
+
+	/// Remove `pane`, giving its space to its neighbors, and renumber the panes after it.
+	///
+	/// Returns whether it was removed, which it isn't if it's the only pane.
+	pub fn remove(&mut self, pane: usize) -> bool {
+		let removed = self.remove_leaf(pane);
+		if removed {
+			self.renumber(pane);
+		}
+		removed
+	}
+
+	fn remove_leaf(&mut self, pane: usize) -> bool {
+		let Split::Split { children, .. } = self else {
+			return false;
+		};
+		if let Some(i) = children.iter().position(|c| *c == Split::Pane(pane)) {
+			children.remove(i);
+		} else if !children.iter_mut().any(|c| c.remove_leaf(pane)) {
+			return false;
+		}
+		if children.len() == 1 {
+			*self = children.remove(0);
+		}
+		true
+	}
+
+	fn renumber(&mut self, removed: usize) {
+		match self {
+			Split::Pane(p) => {
+				if *p > removed {
+					*p -= 1;
+				}
+			}
+			Split::Split { children, .. } => {
+				for child in children {
+					child.renumber(removed);
+				}
+			}
+		}
+	}
+
+	/// Area of the screen taken by each pane
+	pub fn areas(&self, area: Rect) -> Vec<(usize, Rect)> {
+		match self {
+			Split::Pane(p) => vec![(*p, area)],
+			Split::Split { stacked, children } => {
+				let direction = if *stacked {
+					Direction::Vertical
+				} else {
+					Direction::Horizontal
+				};
+				let constraints: Vec<_> = children
+					.iter()
+					.map(|_| Constraint::Ratio(1, children.len() as u32))
+					.collect();
+				let rects = Layout::default()
+					.direction(direction)
+					.constraints(constraints)
+					.split(area);
+				children
+					.iter()
+					.zip(rects)
+					.flat_map(|(child, rect)| child.areas(rect))
+					.collect()
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn splits_and_removes_panes() {
+		let mut split = Split::default();
+		assert!(split.split(0, 1, false));
+		assert!(split.split(0, 2, false));
+		assert!(split.split(1, 3, true));
+		assert!(!split.split(7, 4, true));
+		assert_eq!(vec![0, 2, 1, 3], split.panes());
+
+		let areas = split.areas(Rect::new(0, 0, 90, 20));
+		assert_eq!((0, Rect::new(0, 0, 30, 20)), areas[0]);
+		assert_eq!((2, Rect::new(30, 0, 30, 20)), areas[1]);
+		assert_eq!((1, Rect::new(60, 0, 30, 10)), areas[2]);
+		assert_eq!((3, Rect::new(60, 10, 30, 10)), areas[3]);
+
+		assert!(split.remove(1));
+		assert_eq!(vec![0, 1, 2], split.panes());
+		assert!(split.remove(0));
+		assert!(split.remove(0));
+		assert_eq!(Split::Pane(0), split);
+		assert!(!split.remove(0));
+	}
+}
@@ -0,0 +1,263 @@
+//! Vim-style swap files holding unsaved changes, for recovery after a crash.
+use std::{
+	fs::{self, File},
+	io::{self, BufReader, BufWriter},
+	path::{Path, PathBuf},
+	process,
+	time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+	grid::{Change, ChangeTracker, Grid},
+	watch::Stamp,
+};
+
+/// Minimum time between writes of the swap file
+pub const INTERVAL: Duration = Duration::from_secs(4);
+
+/// Location of the swap file for `file`, a hidden file in the same directory.
+pub fn path_for(file: &Path) -> PathBuf {
+	let name = file
+		.file_name()
+		.map(|n| n.to_string_lossy().into_owned())
+		.unwrap_or_default();
+	file.with_file_name(format!(".{name}.sht.swp"))
+}
+
+/// Delete the swap file at `path`, if it exists.
+pub fn remove(path: &Path) -> io::Result<()> {
+	match fs::remove_file(path) {
+		Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+		r => r,
+	}
+}
+
+/// Changes made to a file that haven't been written to it yet.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Swap {
+	/// Process that wrote the swap file
+	pid: u32,
+	/// The file as it was when the changes were made to it, if it existed
+	#[serde(default)]
+	file: Option<Stamp>,
+	/// Changes to apply to the file on disk, in order
+	changes: Vec<Change>,
+}
+
+impl Swap {
+	/// Changes tracked by `tracker` since `grid` matched the file, last stamped `file`.
+	///
+	/// Fails if the changes replace the whole sheet with one read from a file as it's needed,
+	/// since only the rows in memory would be kept.
+	pub fn new(tracker: &ChangeTracker, grid: &Grid, file: Option<Stamp>) -> io::Result<Self> {
+		let changes = tracker.pending(grid);
+		let lazy = |c: &Change| matches!(c, Change::ReplaceGrid { old } if !old.is_loaded());
+		if changes.iter().any(lazy) {
+			return Err(io::Error::new(
+				io::ErrorKind::Unsupported,
+				"the sheet is too large to keep a copy of in the swap file",
+			));
+		}
+		Ok(Self {
+			pid: process::id(),
+			file,
+			changes,
+		})
+	}
+
+	pub fn read(path: &Path) -> io::Result<Self> {
+		let f = BufReader::new(File::open(path)?);
+		Ok(serde_json::from_reader(f)?)
+	}
+
+	pub fn write(&self, path: &Path) -> io::Result<()> {
+		// write to a temporary file first so a crash can't leave a partial swap
+		let tmp = path.with_extension("swp.tmp");
+		serde_json::to_writer(BufWriter::new(File::create(&tmp)?), self)?;
+		fs::rename(tmp, path)
+	}
+
+	pub fn len(&self) -> usize {
+		self.changes.len()
+	}
+
+	/// Whether the file at `path` has changed since the swap file was written, so its changes
+	/// may not apply to it.
+	pub fn is_outdated(&self, path: &Path) -> bool {
+		self.file
+			.is_some_and(|file| Stamp::of(path).ok() != Some(file))
+	}
+
+	/// Whether the process that wrote the swap file has exited.
+	pub fn is_stale(&self) -> bool {
+		if self.pid == process::id() {
+			return false;
+		}
+		if cfg!(target_os = "linux") {
+			!Path::new("/proc").join(self.pid.to_string()).exists()
+		} else {
+			true
+		}
+	}
+
+	/// Replay the changes onto `grid`, tracking them so they can be undone, or leave it as it was
+	/// if they don't fit it.
+	pub fn apply(self, grid: &mut Grid, tracker: &mut ChangeTracker) -> io::Result<()> {
+		let mut recovered = grid.clone();
+		let undos = self
+			.changes
+			.into_iter()
+			.map(|change| recovered.try_apply(change))
+			.collect::<io::Result<Vec<_>>>()?;
+		*grid = recovered;
+		for undo in undos {
+			undo.track(tracker);
+		}
+		Ok(())
+	}
+
+	/// Describe each change relative to `grid`, the contents of the file on disk.
+	pub fn diff(&self, grid: &Grid) -> io::Result<Vec<String>> {
+		let mut grid = grid.clone();
+		self.changes
+			.iter()
+			.map(|change| {
+				let undo = grid.try_apply(change.clone())?;
+				Ok(describe(change, &undo))
+			})
+			.collect()
+	}
+}
+
+/// Describe an applied change, given the change that reverses it.
+fn describe(change: &Change, undo: &Change) -> String {
+	use Change::*;
+	match (change, undo) {
+		(Replace { pos, old: new }, Replace { old, .. }) => {
+			format!("{},{}: {old:?} -> {new:?}", pos.x + 1, pos.y + 1)
+		}
+		(ReplaceGrid { old: new }, _) => {
+			let size = new.size();
+			format!("Replaced sheet with {}x{} sheet", size.x, size.y)
+		}
+		(DeleteCol { col, old, .. }, _) => format!("Inserted column {}: {old:?}", col + 1),
+		(DeleteRow { row, old }, _) => format!("Inserted row {}: {old:?}", row + 1),
+		(InsertCol { col }, _) => format!("Deleted column {}", col + 1),
+		(InsertRow { row }, _) => format!("Deleted row {}", row + 1),
+		(Batch(changes), _) => format!("Made {} changes", changes.len()),
+		(Replace { pos, .. }, _) => format!("Changed {},{}", pos.x + 1, pos.y + 1),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::XY;
+
+	fn example() -> Grid {
+		let rdr = csv::ReaderBuilder::new()
+			.has_headers(false)
+			.from_reader("a,b\nc,d\n".as_bytes());
+		Grid::from_csv(rdr).unwrap()
+	}
+
+	#[test]
+	fn recovers_pending_changes() {
+		let saved = example();
+		let mut grid = saved.clone();
+		let mut tracker = ChangeTracker::default();
+		grid.edit(XY { x: 1, y: 0 }, "x".into()).track(&mut tracker);
+		grid.insert_row(2, vec!["e".into()]).track(&mut tracker);
+		grid.delete_col(0).track(&mut tracker);
+		tracker.undo(&mut grid);
+
+		let swap = Swap::new(&tracker, &grid, None).unwrap();
+		assert_eq!(2, swap.len());
+
+		let mut recovered = saved.clone();
+		let mut new_tracker = ChangeTracker::default();
+		swap.apply(&mut recovered, &mut new_tracker).unwrap();
+		assert_eq!(grid.to_vec().unwrap(), recovered.to_vec().unwrap());
+		assert!(new_tracker.is_modified());
+	}
+
+	#[test]
+	fn replaces_grid_when_saved_state_unreachable() {
+		let mut grid = example();
+		let mut tracker = ChangeTracker::default();
+		grid.edit(XY { x: 0, y: 0 }, "x".into()).track(&mut tracker);
+		tracker.mark_saved();
+		tracker.undo(&mut grid);
+		grid.edit(XY { x: 0, y: 0 }, "y".into()).track(&mut tracker);
+
+		let mut recovered = example();
+		Swap::new(&tracker, &grid, None)
+			.unwrap()
+			.apply(&mut recovered, &mut ChangeTracker::default())
+			.unwrap();
+		assert_eq!(grid.to_vec().unwrap(), recovered.to_vec().unwrap());
+	}
+
+	#[test]
+	fn refuses_changes_to_different_file() {
+		let mut grid = example();
+		let mut tracker = ChangeTracker::default();
+		grid.insert_row(2, vec!["e".into()]).track(&mut tracker);
+		grid.edit(XY { x: 1, y: 2 }, "f".into()).track(&mut tracker);
+		let swap = Swap::new(&tracker, &grid, None).unwrap();
+
+		let rdr = csv::ReaderBuilder::new()
+			.has_headers(false)
+			.from_reader("a\n".as_bytes());
+		let mut other = Grid::from_csv(rdr).unwrap();
+		let mut other_tracker = ChangeTracker::default();
+		assert!(swap.diff(&other).is_err());
+		assert!(swap.apply(&mut other, &mut other_tracker).is_err());
+		assert_eq!(vec![vec!["a".to_string()]], other.to_vec().unwrap());
+		assert!(!other_tracker.is_modified());
+	}
+
+	#[test]
+	fn notices_file_changed_since() {
+		let path = std::env::temp_dir().join(format!("sht_swap_{}.csv", process::id()));
+		fs::write(&path, "a,b\n").unwrap();
+		let swap = Swap::new(&ChangeTracker::default(), &example(), Stamp::of(&path).ok()).unwrap();
+		assert!(!swap.is_outdated(&path));
+		fs::write(&path, "a,b,c\n").unwrap();
+		assert!(swap.is_outdated(&path));
+		let _ = fs::remove_file(&path);
+	}
+
+	#[test]
+	fn recovers_changes_to_file_read_as_needed() {
+		let path = std::env::temp_dir().join(format!("sht_swap_lazy_{}.csv", process::id()));
+		let text: String = (0..100).map(|y| format!("{y},{y}\n")).collect();
+		fs::write(&path, text).unwrap();
+		let open = || {
+			let mut lazy = Grid::open_csv(&path).unwrap();
+			while !lazy.progress().unwrap().done {
+				std::thread::yield_now();
+			}
+			lazy.refresh();
+			lazy
+		};
+
+		let mut grid = open();
+		let mut tracker = ChangeTracker::default();
+		grid.edit(XY { x: 1, y: 50 }, "x".into())
+			.track(&mut tracker);
+		grid.delete_row(10).track(&mut tracker);
+		let swap = Swap::new(&tracker, &grid, None).unwrap();
+		let mut recovered = open();
+		swap.apply(&mut recovered, &mut ChangeTracker::default())
+			.unwrap();
+		assert_eq!(grid.to_vec().unwrap(), recovered.to_vec().unwrap());
+
+		// the saved state can't be reached, and the sheet to replace it isn't all in memory
+		tracker.forget_saved();
+		assert!(Swap::new(&tracker, &grid, None).is_err());
+		let _ = fs::remove_file(&path);
+	}
+}
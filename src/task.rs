@@ -0,0 +1,198 @@
+//! Reading and writing files on a background thread, so the UI stays responsive.
+use std::{
+	fmt::Display,
+	io::{self, Read},
+	sync::{
+		atomic::{AtomicBool, AtomicU64, Ordering},
+		mpsc::{self, Receiver, TryRecvError},
+		Arc,
+	},
+	thread,
+};
+
+/// What a task's progress is counted in
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Unit {
+	Bytes,
+	Rows,
+}
+
+/// Work done by a task, shared with the thread doing it
+#[derive(Debug, Default)]
+pub struct Counter {
+	done: AtomicU64,
+	cancel: AtomicBool,
+}
+
+impl Counter {
+	pub fn add(&self, n: u64) {
+		self.done.fetch_add(n, Ordering::Relaxed);
+	}
+
+	/// Fail if the task has been cancelled, to stop it at a convenient point.
+	pub fn check(&self) -> io::Result<()> {
+		if self.cancel.load(Ordering::Relaxed) {
+			return Err(io::Error::other("Cancelled"));
+		}
+		Ok(())
+	}
+}
+
+/// Work running on a background thread
+#[derive(Debug)]
+pub struct Task<T> {
+	counter: Arc<Counter>,
+	unit: Unit,
+	total: u64,
+	result: Receiver<io::Result<T>>,
+}
+
+impl<T: Send + 'static> Task<T> {
+	/// Run `f` on a new thread, expecting it to count up to `total` of `unit`.
+	pub fn spawn(
+		name: &str,
+		unit: Unit,
+		total: u64,
+		f: impl FnOnce(&Counter) -> io::Result<T> + Send + 'static,
+	) -> io::Result<Self> {
+		let counter = Arc::<Counter>::default();
+		let (tx, result) = mpsc::channel();
+		{
+			let counter = counter.clone();
+			thread::Builder::new()
+				.name(name.to_string())
+				.spawn(move || {
+					let _ = tx.send(f(&counter));
+				})?;
+		}
+		Ok(Self {
+			counter,
+			unit,
+			total,
+			result,
+		})
+	}
+
+	/// Ask the task to stop, which it does with an error.
+	pub fn cancel(&self) {
+		self.counter.cancel.store(true, Ordering::Relaxed);
+	}
+
+	/// The result of the task, if it has finished
+	pub fn poll(&self) -> Option<io::Result<T>> {
+		match self.result.try_recv() {
+			Ok(result) => Some(result),
+			Err(TryRecvError::Empty) => None,
+			Err(TryRecvError::Disconnected) => Some(Err(io::Error::other("Task panicked"))),
+		}
+	}
+
+	pub fn progress(&self) -> Progress {
+		Progress {
+			unit: self.unit,
+			done: self.counter.done.load(Ordering::Relaxed),
+			total: self.total,
+		}
+	}
+}
+
+/// How far along a task is, for display
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Progress {
+	pub unit: Unit,
+	pub done: u64,
+	pub total: u64,
+}
+
+impl Display for Progress {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		let percent = (self.done * 100).checked_div(self.total).unwrap_or(100);
+		write!(f, "{}% (", percent.min(100))?;
+		match self.unit {
+			Unit::Bytes => write!(f, "{} of {}", Bytes(self.done), Bytes(self.total))?,
+			Unit::Rows => write!(f, "{} of {} rows", self.done, self.total)?,
+		}
+		write!(f, ")")
+	}
+}
+
+/// Size in bytes, formatted with a binary prefix
+pub struct Bytes(pub u64);
+
+impl Display for Bytes {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+		if self.0 < 1024 {
+			return write!(f, "{} B", self.0);
+		}
+		let mut size = self.0 as f64 / 1024.0;
+		let mut unit = 0;
+		while size >= 1024.0 && unit < UNITS.len() - 1 {
+			size /= 1024.0;
+			unit += 1;
+		}
+		write!(f, "{size:.1} {}", UNITS[unit])
+	}
+}
+
+/// Reader that counts the bytes read from it and stops when the task is cancelled
+pub struct Counted<'c, R> {
+	inner: R,
+	counter: &'c Counter,
+}
+
+impl<'c, R> Counted<'c, R> {
+	pub fn new(inner: R, counter: &'c Counter) -> Self {
+		Self { inner, counter }
+	}
+}
+
+impl<R: Read> Read for Counted<'_, R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		self.counter.check()?;
+		let n = self.inner.read(buf)?;
+		self.counter.add(n as u64);
+		Ok(n)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn formats_progress() {
+		let progress = Progress {
+			unit: Unit::Bytes,
+			done: 3 << 19,
+			total: 3 << 20,
+		};
+		assert_eq!("50% (1.5 MiB of 3.0 MiB)", progress.to_string());
+		let progress = Progress {
+			unit: Unit::Rows,
+			done: 5,
+			total: 0,
+		};
+		assert_eq!("100% (5 of 0 rows)", progress.to_string());
+	}
+
+	#[test]
+	fn cancels_reads() {
+		let task = Task::<()>::spawn("test", Unit::Bytes, 0, |counter| {
+			let mut rdr = Counted::new(io::repeat(0), counter);
+			let mut buf = [0; 16];
+			loop {
+				rdr.read_exact(&mut buf)?;
+			}
+		})
+		.unwrap();
+		task.cancel();
+		let result = loop {
+			if let Some(result) = task.poll() {
+				break result;
+			}
+			thread::yield_now();
+		};
+		assert_eq!("Cancelled", result.unwrap_err().to_string());
+	}
+}
@@ -6,8 +6,14 @@ use std::{
 use crossterm::event::KeyCode;
 use serde::{Deserialize, Serialize};
 use tui::{layout::Rect, style::Style, widgets::StatefulWidget};
+use unicode_width::UnicodeWidthStr;
 
-use crate::{bindings::Bindings, input::Input, program::Direction, XY};
+use crate::{
+	bindings::{Bindings, Conflict},
+	input::Input,
+	program::Direction,
+	XY,
+};
 
 use super::Dialog;
 
@@ -37,22 +43,26 @@ pub enum EditAction {
 
 impl EditAction {
 	pub fn bindings() -> Bindings<Self> {
+		Self::defaults().expect("Edit bindings conflict")
+	}
+
+	fn defaults() -> Result<Bindings<Self>, Conflict<Self>> {
 		let mut b = Bindings::empty();
 		use EditAction as A;
 		use KeyCode::*;
 
-		b.insert(Esc.into(), A::Cancel);
-		b.insert(Enter.into(), A::Enter);
-		b.insert(Backspace.into(), A::Backspace);
-		b.insert(Delete.into(), A::Delete);
-		b.insert(Left.into(), A::Move(Direction::Left));
-		b.insert(Right.into(), A::Move(Direction::Right));
-		b.insert(Up.into(), A::Move(Direction::Up));
-		b.insert(Down.into(), A::Move(Direction::Down));
-		b.insert(Home.into(), A::Jump(Direction::Left));
-		b.insert(End.into(), A::Jump(Direction::Right));
+		b.insert(Esc.into(), A::Cancel)?;
+		b.insert(Enter.into(), A::Enter)?;
+		b.insert(Backspace.into(), A::Backspace)?;
+		b.insert(Delete.into(), A::Delete)?;
+		b.insert(Left.into(), A::Move(Direction::Left))?;
+		b.insert(Right.into(), A::Move(Direction::Right))?;
+		b.insert(Up.into(), A::Move(Direction::Up))?;
+		b.insert(Down.into(), A::Move(Direction::Down))?;
+		b.insert(Home.into(), A::Jump(Direction::Left))?;
+		b.insert(End.into(), A::Jump(Direction::Right))?;
 
-		b
+		Ok(b)
 	}
 }
 
@@ -60,7 +70,7 @@ impl EditAction {
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct EditState {
 	buffer: String,
-	/// [0, buffer.len()]
+	/// [0, buffer.len()], always on a char boundary
 	cursor: usize,
 }
 
@@ -78,6 +88,22 @@ impl EditState {
 		&self.buffer
 	}
 
+	/// Byte index of the start of the character left of the cursor
+	fn prev_boundary(&self) -> Option<usize> {
+		self.buffer[..self.cursor]
+			.char_indices()
+			.next_back()
+			.map(|(i, _c)| i)
+	}
+
+	/// Byte index of the end of the character right of the cursor
+	fn next_boundary(&self) -> Option<usize> {
+		self.buffer[self.cursor..]
+			.chars()
+			.next()
+			.map(|c| self.cursor + c.len_utf8())
+	}
+
 	/// Remove the character right of the cursor.
 	fn pop_char_right(&mut self) {
 		if self.cursor >= self.buffer.len() {
@@ -88,34 +114,32 @@ impl EditState {
 
 	/// Remove the character left of the cursor.
 	fn pop_char_left(&mut self) {
-		if self.cursor <= 0 {
+		let Some(prev) = self.prev_boundary() else {
 			return;
-		}
-		self.buffer.remove(self.cursor - 1);
-		self.cursor -= 1;
+		};
+		self.buffer.remove(prev);
+		self.cursor = prev;
 	}
 
 	/// Insert a character at the current position.
 	fn insert_char(&mut self, c: char) {
 		self.buffer.insert(self.cursor, c);
-		self.cursor += 1;
+		self.cursor += c.len_utf8();
 	}
 
 	fn move_left(&mut self) {
-		if self.cursor <= 0 {
-			return;
+		if let Some(prev) = self.prev_boundary() {
+			self.cursor = prev;
 		}
-		self.cursor -= 1;
 	}
 
 	fn move_right(&mut self) {
-		if self.cursor >= self.buffer.len() {
-			return;
+		if let Some(next) = self.next_boundary() {
+			self.cursor = next;
 		}
-		self.cursor += 1;
 	}
 
-	fn move_beginning(&mut self) {
+	pub fn move_beginning(&mut self) {
 		self.cursor = 0;
 	}
 
@@ -149,7 +173,7 @@ impl EditState {
 	/// Position of the editing cursor if the view is rendered in area.
 	pub fn cursor(&self, area: Rect) -> XY<u16> {
 		XY {
-			x: area.x + self.cursor as u16,
+			x: area.x + self.buffer[..self.cursor].width() as u16,
 			y: area.y,
 		}
 	}
@@ -196,6 +220,6 @@ impl Dialog<EditAction> for &mut EditState {
 			Char(c) => self.insert_char(c),
 		}
 
-		return Continue(());
+		Continue(())
 	}
 }
@@ -1,4 +1,4 @@
-use std::cmp::max;
+use std::{cmp::max, collections::BTreeMap};
 
 use tui::{
 	buffer::Buffer,
@@ -6,19 +6,26 @@ use tui::{
 	widgets::{StatefulWidget, Widget},
 };
 
-use crate::Grid;
+use crate::{Grid, XY};
 
-use super::{Table, TableState};
+use super::{Cells, Table, TableState};
 
 pub type GridState = TableState;
 
 pub struct GridView<'g> {
 	grid: &'g Grid,
+	widths: Option<&'g BTreeMap<usize, u16>>,
 }
 
 impl<'g> GridView<'g> {
 	pub fn new(grid: &'g Grid) -> Self {
-		Self { grid }
+		Self { grid, widths: None }
+	}
+
+	/// Use these widths for their columns instead of fitting their contents.
+	pub fn widths(mut self, widths: &'g BTreeMap<usize, u16>) -> Self {
+		self.widths = Some(widths);
+		self
 	}
 }
 
@@ -26,27 +33,20 @@ impl<'g> StatefulWidget for GridView<'g> {
 	type State = GridState;
 
 	fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
-		let table = Table::new(self.grid.cells());
-		// use longest width
-		let width = self
-			.grid
-			.cells()
-			.first()
-			.map(|r| r.len())
-			.unwrap_or_default();
-		let constraints = self
-			.grid
-			.cells()
-			.iter()
-			.fold(vec![0; width], |mut len, row| {
-				for (i, cell) in row.iter().enumerate() {
-					len[i] = max(len[i], cell.len());
-				}
-				len
-			})
+		let table = Table::new(self.grid);
+		// use longest width of the cells that are available
+		let mut widths = vec![0; self.grid.size().x];
+		self.grid
+			.visit_loaded(|x, cell| widths[x] = max(widths[x], cell.len()));
+		let constraints = widths
 			.into_iter()
 			.map(|l| l.try_into().expect("assume cell width less that u16 max"))
 			// .map(|l| max(l, 16))
+			.enumerate()
+			.map(|(x, l)| match self.widths.and_then(|w| w.get(&x)) {
+				Some(&width) => width,
+				None => l,
+			})
 			.collect::<Vec<_>>();
 
 		let table = table.with_widths(&constraints);
@@ -55,6 +55,16 @@ impl<'g> StatefulWidget for GridView<'g> {
 	}
 }
 
+impl Cells for Grid {
+	fn height(&self) -> usize {
+		self.size().y
+	}
+
+	fn cell(&self, pos: XY<usize>) -> Option<&str> {
+		self.get(pos).map(String::as_str)
+	}
+}
+
 impl<'g> Widget for GridView<'g> {
 	fn render(self, area: Rect, buf: &mut Buffer) {
 		let mut state = GridState::default();
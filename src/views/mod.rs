@@ -7,9 +7,14 @@ pub use debug::*;
 mod grid;
 pub use grid::*;
 mod table;
+pub use table::DEFAULT_WIDTH;
 use table::*;
 mod palette;
 pub use palette::*;
+mod prompt;
+pub use prompt::*;
+mod text_input;
+pub use text_input::*;
 
 use crate::input;
 
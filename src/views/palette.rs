@@ -7,10 +7,8 @@ use std::{
 };
 
 use crossterm::event::{KeyCode, KeyModifiers};
-use enum_iterator;
 use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
 use serde::{Deserialize, Serialize};
-use strum::EnumMessage;
 use tui::{
 	buffer::Buffer,
 	layout::{Alignment, Constraint, Direction::Vertical, Layout, Rect},
@@ -31,12 +29,17 @@ use super::{Dialog, EditState, EditView};
 
 type Item = (Option<InputBuffer>, Action);
 
+/// Action chosen from the palette, with what was typed for its argument if it takes one
+pub type Choice = (Action, Option<String>);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PaletteState {
 	items: Vec<Item>,
 	edit: EditState,
 	#[serde(skip)]
 	list: ListState,
+	/// Chosen action whose argument is being typed, instead of searching for one
+	argument: Option<Action>,
 }
 
 impl PaletteState {
@@ -48,6 +51,17 @@ impl PaletteState {
 			items,
 			list,
 			edit: Default::default(),
+			argument: None,
+		}
+	}
+
+	/// Ask for the argument of `action`.
+	pub fn argument(action: Action) -> Self {
+		Self {
+			items: Vec::new(),
+			list: ListState::default(),
+			edit: Default::default(),
+			argument: Some(action),
 		}
 	}
 
@@ -65,7 +79,7 @@ impl PaletteState {
 		let matcher = SkimMatcherV2::default();
 		self.items
 			.iter()
-			.filter(move |(_i, a)| is_empty || matcher.fuzzy_match(a.desc(), query).is_some())
+			.filter(move |(_i, a)| is_empty || matcher.fuzzy_match(&a.desc(), query).is_some())
 		// TODO: order by weight
 	}
 
@@ -139,8 +153,12 @@ impl StatefulWidget for PaletteView {
 		{
 			let area = input;
 			Clear.render(area, buf);
+			let title = match state.argument {
+				Some(action) => action.desc(),
+				None => "Command Palette".to_string(),
+			};
 			let block = Block::default()
-				.title("Command Palette")
+				.title(title)
 				.title_alignment(Alignment::Center)
 				.borders(Borders::ALL);
 			let inner = block.inner(area);
@@ -150,16 +168,23 @@ impl StatefulWidget for PaletteView {
 		// search results, highlighted
 		{
 			let block = Block::default().borders(Borders::BOTTOM | Borders::LEFT | Borders::RIGHT);
+			if let Some(prompt) = state.argument.and_then(|a| a.argument()) {
+				let mut area = results;
+				area.height = min(area.height, 2);
+				Clear.render(area, buf);
+				Paragraph::new(prompt)
+					.block(block)
+					.alignment(Alignment::Center)
+					.render(area, buf);
+				return;
+			}
 			let mut area = results;
 			let borders_height = 1;
 			let borders_width = 2;
 			let items: Vec<_> = state
 				.matching()
 				.map(|(i, a)| {
-					let mut desc = a
-						.get_documentation()
-						.map(|d| d.to_string())
-						.unwrap_or_else(|| format!("{:?}", a));
+					let mut desc = a.desc();
 					let bind = i.to_owned().map(|i| i.to_string()).unwrap_or_default();
 					let min_sep = 1;
 					let desc_width =
@@ -174,7 +199,7 @@ impl StatefulWidget for PaletteView {
 
 					ListItem::new(Spans::from(vec![
 						Span::raw(desc),
-						Span::raw(String::from_iter(iter::repeat(' ').take(spacing))),
+						Span::raw(String::from_iter(iter::repeat_n(' ', spacing))),
 						Span::styled(bind, styles::keybind()),
 					]))
 				})
@@ -201,9 +226,18 @@ impl StatefulWidget for PaletteView {
 }
 
 impl Dialog for &mut PaletteState {
-	type Output = Option<Action>;
+	type Output = Option<Choice>;
 
 	fn handle_input(self, key: Input) -> ControlFlow<Self::Output> {
+		if let Some(action) = self.argument {
+			return match key {
+				Input(KeyCode::Enter, ..) => Break(Some((action, Some(self.edit.take())))),
+				_ => match self.edit.handle_input(key) {
+					Break(_) => Break(None),
+					Continue(()) => Continue(()),
+				},
+			};
+		}
 		match key {
 			Input(KeyCode::Up, ..) => {
 				self.move_up();
@@ -226,7 +260,14 @@ impl Dialog for &mut PaletteState {
 				self.reset_selection();
 			}
 			Input(KeyCode::Enter, ..) => {
-				return Break(self.selected());
+				return match self.selected() {
+					Some(action) if action.argument().is_some() => {
+						self.argument = Some(action);
+						self.edit = Default::default();
+						Continue(())
+					}
+					action => Break(action.map(|a| (a, None))),
+				};
 			}
 			_ => {}
 		}
@@ -0,0 +1,121 @@
+use std::{
+	marker::PhantomData,
+	ops::ControlFlow::{self, *},
+};
+
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+use tui::{
+	buffer::Buffer,
+	layout::{Alignment, Rect},
+	text::{Span, Spans},
+	widgets::{Block, Borders, Clear, Paragraph, StatefulWidget, Widget, Wrap},
+};
+
+use crate::{input::Input, styles};
+
+use super::Dialog;
+
+/// A question with a fixed set of answers, each chosen with a single key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptState<T> {
+	title: String,
+	body: Vec<String>,
+	choices: Vec<(char, String, T)>,
+}
+
+impl<T: Clone> PromptState<T> {
+	pub fn new(title: impl Into<String>) -> Self {
+		Self {
+			title: title.into(),
+			body: Vec::new(),
+			choices: Vec::new(),
+		}
+	}
+
+	pub fn body(mut self, lines: impl IntoIterator<Item = String>) -> Self {
+		self.body = lines.into_iter().collect();
+		self
+	}
+
+	pub fn set_body(&mut self, lines: impl IntoIterator<Item = String>) {
+		self.body = lines.into_iter().collect();
+	}
+
+	pub fn choice(mut self, key: char, desc: impl Into<String>, value: T) -> Self {
+		self.choices.push((key, desc.into(), value));
+		self
+	}
+
+	/// Height of the prompt drawn in an area `width` wide
+	pub fn height(&self, width: u16) -> u16 {
+		let inner = width.saturating_sub(2).max(1) as usize;
+		let body: usize = self
+			.body
+			.iter()
+			.map(|l| l.chars().count().max(1).div_ceil(inner))
+			.sum();
+		let choices: usize = self
+			.choices
+			.iter()
+			.map(|(_, desc, _)| desc.chars().count() + 6)
+			.sum();
+		(body + 3 + choices.max(1).div_ceil(inner)) as u16
+	}
+}
+
+#[derive(Debug)]
+pub struct PromptView<T>(PhantomData<T>);
+
+impl<T> Default for PromptView<T> {
+	fn default() -> Self {
+		Self(PhantomData)
+	}
+}
+
+impl<T> StatefulWidget for PromptView<T> {
+	type State = PromptState<T>;
+
+	fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+		let mut text: Vec<_> = state.body.iter().map(|l| Spans::from(l.as_str())).collect();
+		text.push(Spans::default());
+		let mut choices = Vec::new();
+		for (i, (key, desc, _)) in state.choices.iter().enumerate() {
+			if i != 0 {
+				choices.push(Span::raw("  "));
+			}
+			choices.push(Span::styled(format!("[{key}]"), styles::keybind()));
+			choices.push(Span::raw(format!(" {desc}")));
+		}
+		text.push(Spans::from(choices));
+
+		Clear.render(area, buf);
+		Paragraph::new(text)
+			.block(
+				Block::default()
+					.title(format!(" {} ", state.title))
+					.title_alignment(Alignment::Center)
+					.borders(Borders::ALL),
+			)
+			.wrap(Wrap { trim: false })
+			.render(area, buf);
+	}
+}
+
+impl<T: Clone> Dialog for &mut PromptState<T> {
+	type Output = Option<T>;
+
+	fn handle_input(self, key: Input) -> ControlFlow<Self::Output> {
+		match key {
+			Input(KeyCode::Esc, ..) => Break(None),
+			Input(KeyCode::Char(c), ..) => {
+				let c = c.to_ascii_lowercase();
+				match self.choices.iter().find(|(k, ..)| *k == c) {
+					Some((.., v)) => Break(Some(v.clone())),
+					None => Continue(()),
+				}
+			}
+			_ => Continue(()),
+		}
+	}
+}
@@ -11,10 +11,19 @@ use tui::{
 
 use crate::{styles, Rect as MyRect, XY};
 
-const DEFAULT_WIDTH: u16 = 12;
+/// Width of a column without a width of its own
+pub const DEFAULT_WIDTH: u16 = 12;
+
+/// Contents of a `Table`
+pub trait Cells {
+	/// Number of rows
+	fn height(&self) -> usize;
+	/// Contents of a cell, if it exists and is available
+	fn cell(&self, pos: XY<usize>) -> Option<&str>;
+}
 
 /// A widget to display data in formatted columns.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Clone)]
 pub struct Table<'a> {
 	/// Base style for the widget
 	style: Style,
@@ -31,15 +40,17 @@ pub struct Table<'a> {
 	odd_row_style: Style,
 	/// Style used to render the selected row
 	highlight_style: Style,
+	/// Style used to render cells in the selected range
+	range_style: Style,
 	// /// Optional header
 	// TODO: Frozen headers/columns
 	// header: Option<usize>,
 	/// Data to display in each row
-	rows: &'a Vec<Vec<String>>,
+	cells: &'a dyn Cells,
 }
 
 impl<'a> Table<'a> {
-	pub fn new(rows: &'a Vec<Vec<String>>) -> Self {
+	pub fn new(cells: &'a dyn Cells) -> Self {
 		Self {
 			style: styles::grid(),
 			// TODO: own this, use index-based method or expose default?
@@ -51,12 +62,13 @@ impl<'a> Table<'a> {
 			// odd_row_style: Style::default().bg(Color::Black).fg(Color::White),
 			// odd_row_style: Style::default().add_modifier(Modifier::UNDERLINED),
 			highlight_style: styles::selected(),
-			rows,
+			range_style: styles::selected_range(),
+			cells,
 		}
 	}
 
 	pub fn with_widths(mut self, widths: &'a [u16]) -> Self {
-		self.widths = widths.as_ref();
+		self.widths = widths;
 		self
 	}
 }
@@ -82,7 +94,7 @@ impl<'a> Table<'a> {
 		}
 
 		let Some(selected) = selected else {
-			return (start, end, height> max_height);
+			return (start, end, height > max_height);
 		};
 
 		while selected >= end {
@@ -116,13 +128,6 @@ impl<'a> Table<'a> {
 		self.cell_widths().map(|w| w + self.column_spacing)
 	}
 
-	fn cell_width_at(&self, col: usize) -> u16 {
-		self.widths
-			.get(col)
-			.map(ToOwned::to_owned)
-			.unwrap_or(DEFAULT_WIDTH)
-	}
-
 	fn col_width_at(&self, col: usize) -> u16 {
 		self.widths.get(col).unwrap_or(&DEFAULT_WIDTH) + self.column_spacing
 	}
@@ -199,15 +204,12 @@ impl<'a> Table<'a> {
 pub struct TableState {
 	offset: XY<usize>,
 	selected: Option<XY<usize>>,
+	selected_range: Option<MyRect<usize>>,
 	selected_area: Option<MyRect<u16>>,
 	visible_cells: XY<usize>,
 }
 
 impl TableState {
-	pub fn selected(&self) -> Option<XY<usize>> {
-		self.selected
-	}
-
 	pub fn select(&mut self, index: Option<XY<usize>>) {
 		self.selected = index;
 		if index.is_none() {
@@ -215,6 +217,11 @@ impl TableState {
 		}
 	}
 
+	/// Highlight a range of cells in addition to the selected cell
+	pub fn select_range(&mut self, range: Option<MyRect<usize>>) {
+		self.selected_range = range;
+	}
+
 	pub fn scroll_mut(&mut self) -> &mut XY<usize> {
 		&mut self.offset
 	}
@@ -238,11 +245,8 @@ impl<'a> StatefulWidget for Table<'a> {
 	type State = TableState;
 
 	fn render(self, area: tui::layout::Rect, buf: &mut Buffer, state: &mut Self::State) {
-		if let Some(r) = self.rows.first() {
-			let width = r.len();
-			assert!(self.widths.len() == width);
-		}
 		// TODO: handle constraining/reseting
+		state.selected_area = None;
 		if area.area() == 0 {
 			return;
 		}
@@ -250,7 +254,7 @@ impl<'a> StatefulWidget for Table<'a> {
 		buf.set_style(area, self.style);
 
 		// Draw rows
-		if self.rows.is_empty() {
+		if self.cells.height() == 0 {
 			return;
 		}
 
@@ -310,13 +314,14 @@ impl<'a> StatefulWidget for Table<'a> {
 					}
 				}
 				cell_area = cell_area.intersection(table_row_area);
-				if let Some(cell) = self.rows.get(row_t).and_then(|r| r.get(col_t)) {
+				if let Some(cell) = self.cells.cell(XY { x: col_t, y: row_t }) {
 					render_cell(buf, cell, cell_area);
 				}
-				let is_selected = state
-					.selected
-					.map(|s| s == XY { x: col_t, y: row_t })
-					.unwrap_or_default();
+				let pos = XY { x: col_t, y: row_t };
+				if state.selected_range.is_some_and(|r| r.contains(pos)) {
+					buf.set_style(cell_area, self.range_style);
+				}
+				let is_selected = state.selected.map(|s| s == pos).unwrap_or_default();
 				if is_selected {
 					buf.set_style(cell_area, self.highlight_style);
 					state.selected_area = Some(cell_area.try_into().unwrap());
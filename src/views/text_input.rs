@@ -0,0 +1,66 @@
+use std::ops::ControlFlow;
+
+use serde::{Deserialize, Serialize};
+use tui::{
+	buffer::Buffer,
+	layout::{Alignment, Rect},
+	widgets::{Block, Borders, Clear, StatefulWidget, Widget},
+};
+
+use crate::{input::Input, XY};
+
+use super::{Dialog, EditState, EditView};
+
+/// A single line of text requested from the user.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputState {
+	title: String,
+	edit: EditState,
+}
+
+impl InputState {
+	pub fn new(title: impl Into<String>, initial: &str) -> Self {
+		Self {
+			title: title.into(),
+			edit: EditState::from_str(initial),
+		}
+	}
+
+	pub fn cursor(&self, area: Rect) -> XY<u16> {
+		let mut c = self.edit.cursor(area);
+		c.x += 1;
+		c.y += 1;
+		c
+	}
+}
+
+#[derive(Default, Debug)]
+pub struct InputView {}
+
+impl InputView {
+	/// Height of the view, including borders
+	pub const HEIGHT: u16 = 3;
+}
+
+impl StatefulWidget for InputView {
+	type State = InputState;
+
+	fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+		Clear.render(area, buf);
+		let block = Block::default()
+			.title(format!(" {} ", state.title))
+			.title_alignment(Alignment::Center)
+			.borders(Borders::ALL);
+		let inner = block.inner(area);
+		block.render(area, buf);
+		EditView::default().render(inner, buf, &mut state.edit);
+	}
+}
+
+impl Dialog for &mut InputState {
+	type Output = Option<String>;
+
+	fn handle_input(self, key: Input) -> ControlFlow<Self::Output> {
+		self.edit.handle_input(key)
+	}
+}
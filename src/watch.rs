@@ -0,0 +1,81 @@
+//! Noticing when the file being edited is changed by another program.
+use std::{
+	fs, io,
+	path::{Path, PathBuf},
+	sync::mpsc::{self, Receiver},
+	time::SystemTime,
+};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher as _};
+use serde::{Deserialize, Serialize};
+
+/// Size and modification time of a file, to tell whether it changed since it was last read or
+/// written
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Stamp {
+	pub len: u64,
+	pub modified: Option<SystemTime>,
+}
+
+impl Stamp {
+	pub fn of(path: &Path) -> io::Result<Self> {
+		let metadata = fs::metadata(path)?;
+		Ok(Self {
+			len: metadata.len(),
+			modified: metadata.modified().ok(),
+		})
+	}
+}
+
+/// Watches the directory of a file for changes to it, including it being replaced by a rename
+#[derive(Debug)]
+pub struct Watcher {
+	_watcher: RecommendedWatcher,
+	events: Receiver<notify::Result<Event>>,
+	path: PathBuf,
+}
+
+fn to_io(e: notify::Error) -> io::Error {
+	match e.kind {
+		notify::ErrorKind::Io(e) => e,
+		_ => io::Error::other(e.to_string()),
+	}
+}
+
+impl Watcher {
+	pub fn new(path: &Path) -> io::Result<Self> {
+		let name = path
+			.file_name()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Not a file"))?;
+		let dir = match path.parent() {
+			Some(dir) if !dir.as_os_str().is_empty() => dir,
+			_ => Path::new("."),
+		}
+		.canonicalize()?;
+
+		let (tx, events) = mpsc::channel();
+		let mut watcher = notify::recommended_watcher(tx).map_err(to_io)?;
+		watcher
+			.watch(&dir, RecursiveMode::NonRecursive)
+			.map_err(to_io)?;
+		Ok(Self {
+			_watcher: watcher,
+			events,
+			path: dir.join(name),
+		})
+	}
+
+	/// Whether the file has been modified, created, or removed since the last call
+	pub fn changed(&self) -> bool {
+		let mut changed = false;
+		for event in self.events.try_iter() {
+			match event {
+				Ok(event) => {
+					changed |= !event.kind.is_access() && event.paths.contains(&self.path);
+				}
+				Err(e) => warn!("Error watching {:?}: {e}", self.path),
+			}
+		}
+		changed
+	}
+}